@@ -1,47 +1,240 @@
 use clap::{App, Arg};
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, StdoutLock, Write};
+
+/// Returned by `run` when it failed after reporting each error itself
+#[derive(Debug)]
+pub struct AlreadyReported;
+
+impl fmt::Display for AlreadyReported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "errors were reported")
+    }
+}
+
+impl Error for AlreadyReported {}
+
+// Records whether writing failed, so that the error isn't blamed on the
+// input being read
+struct Output<W> {
+    inner: W,
+    failed: bool,
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf).inspect_err(|_| self.failed = true)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().inspect_err(|_| self.failed = true)
+    }
+}
 
 #[derive(Debug)]
 pub struct Config {
     pub files: Vec<String>,
     pub number_lines: bool,
     pub number_nonblank_lines: bool,
+    pub show_ends: bool,
+    pub show_tabs: bool,
+    pub show_nonprinting: bool,
+    pub squeeze_blank: bool,
+    pub number_start: i64,
+    pub number_increment: i64,
+    pub number_width: usize,
+    pub number_separator: String,
+    pub number_format: NumberFormat,
+    pub section_delimiter: Option<String>,
+}
+
+// Alignment of line numbers, named as in nl
+#[derive(Debug, PartialEq)]
+pub enum NumberFormat {
+    LeftJustified,
+    RightJustified,
+    RightZeroPadded,
+}
+
+// Output state carried from one file to the next, as GNU cat does
+struct State {
+    line_num: i64,
+    prev_blank: bool,
+    at_line_start: bool,
 }
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 pub fn run(config: Config) -> MyResult<()> {
-    for filename in config.files {
-        match open(&filename) {
-            Err(err) => eprintln!("Failed to open {}: {}", filename, err),
-            Ok(handle) => {
-                if config.number_lines {
-                    handle
-                        .lines()
-                        .enumerate()
-                        .for_each(|(i, l)| println!("{:>6}\t{}", i + 1, l.unwrap()))
-                } else if config.number_nonblank_lines {
-                    let mut i = 0;
-                    for l in handle.lines() {
-                        if l.as_ref().unwrap().is_empty() {
-                            println!("");
-                            continue;
-                        } else {
-                            i += 1;
-                            println!("{:>6}\t{}", i, l.unwrap())
-                        }
+    let stdout = io::stdout();
+    let mut out = Output {
+        inner: BufWriter::new(stdout.lock()),
+        failed: false,
+    };
+    let mut state = State {
+        line_num: config.number_start,
+        prev_blank: false,
+        at_line_start: true,
+    };
+    let plain = !(config.number_lines
+        || config.number_nonblank_lines
+        || config.show_ends
+        || config.show_tabs
+        || config.show_nonprinting
+        || config.squeeze_blank);
+    let mut failed = false;
+    for filename in &config.files {
+        let result = if plain {
+            match filename.as_str() {
+                "-" => copy(&mut io::stdin().lock(), &mut out),
+                _ => match File::open(filename) {
+                    Err(err) => {
+                        eprintln!("Failed to open {}: {}", filename, err);
+                        failed = true;
+                        continue;
                     }
-                } else {
-                    handle.lines().for_each(|l| println!("{}", l.unwrap()))
+                    Ok(mut file) => copy(&mut file, &mut out),
+                },
+            }
+        } else {
+            match open(filename) {
+                Err(err) => {
+                    eprintln!("Failed to open {}: {}", filename, err);
+                    failed = true;
+                    continue;
                 }
+                Ok(handle) => cat_lines(handle, &config, &mut state, &mut out),
+            }
+        };
+        match result {
+            Err(err) if out.failed => return write_error(err),
+            // Opening a directory succeeds, so EISDIR shows up here
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", filename, err);
+                failed = true;
             }
+            Ok(()) => {}
         }
     }
+    match out.flush() {
+        Err(err) => write_error(err),
+        Ok(()) if failed => Err(Box::new(AlreadyReported)),
+        Ok(()) => Ok(()),
+    }
+}
+
+// Writing can't go on, so stop at once
+fn write_error(err: io::Error) -> MyResult<()> {
+    // The reader has gone away, as when piped into head
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        return Ok(());
+    }
+    eprintln!("write error: {}", err);
+    Err(Box::new(AlreadyReported))
+}
+
+// Concrete reader and writer types let io::copy use copy_file_range or
+// sendfile on Linux, but those can't say which side failed. After an
+// error the rest is copied the ordinary way, which can.
+fn copy<R: Read>(reader: &mut R, out: &mut Output<BufWriter<StdoutLock>>) -> io::Result<()> {
+    if io::copy(reader, &mut out.inner).is_ok() {
+        return Ok(());
+    }
+    io::copy(reader, out).map(drop)
+}
+
+fn cat_lines(
+    mut handle: impl BufRead,
+    config: &Config,
+    state: &mut State,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    let mut formatted = Vec::new();
+    while handle.read_until(b'\n', &mut line)? > 0 {
+        let (content, newline) = match line.strip_suffix(b"\n") {
+            Some(content) => (content, true),
+            None => (&line[..], false),
+        };
+        // The rest of a line continued from the end of the previous file
+        let continued = !state.at_line_start;
+        let blank = content.is_empty() && !continued;
+        let section = !continued
+            && config
+                .section_delimiter
+                .as_ref()
+                .is_some_and(|delim| delim.as_bytes() == content);
+        if section {
+            state.line_num = config.number_start;
+        } else if !(config.squeeze_blank && blank && state.prev_blank) {
+            if !continued && (config.number_lines || (config.number_nonblank_lines && !blank)) {
+                write!(
+                    out,
+                    "{}{}",
+                    format_number(state.line_num, config),
+                    config.number_separator
+                )?;
+                state.line_num += config.number_increment;
+            }
+            formatted.clear();
+            show_bytes(content, config, &mut formatted);
+            if newline {
+                if config.show_ends {
+                    formatted.push(b'$');
+                }
+                formatted.push(b'\n');
+            }
+            out.write_all(&formatted)?;
+        }
+        if !section {
+            state.prev_blank = blank;
+        }
+        state.at_line_start = newline;
+        line.clear();
+    }
     Ok(())
 }
 
+fn format_number(num: i64, config: &Config) -> String {
+    let width = config.number_width;
+    match config.number_format {
+        NumberFormat::LeftJustified => format!("{:<width$}", num),
+        NumberFormat::RightJustified => format!("{:>width$}", num),
+        NumberFormat::RightZeroPadded => format!("{:0width$}", num),
+    }
+}
+
+// Makes tabs and nonprinting bytes visible as requested, using the ^ and
+// M- notation of GNU cat
+fn show_bytes(bytes: &[u8], config: &Config, out: &mut Vec<u8>) {
+    for &byte in bytes {
+        if byte == b'\t' {
+            if config.show_tabs {
+                out.extend_from_slice(b"^I");
+            } else {
+                out.push(byte);
+            }
+            continue;
+        }
+        if !config.show_nonprinting {
+            out.push(byte);
+            continue;
+        }
+        let mut byte = byte;
+        if byte >= 128 {
+            out.extend_from_slice(b"M-");
+            byte -= 128;
+        }
+        match byte {
+            0..=31 => out.extend_from_slice(&[b'^', byte + 64]),
+            127 => out.extend_from_slice(b"^?"),
+            _ => out.push(byte),
+        }
+    }
+}
+
 pub fn get_args() -> MyResult<Config> {
     let matches = App::new("catr")
         .version("0.1.0")
@@ -70,18 +263,149 @@ pub fn get_args() -> MyResult<Config> {
                 .takes_value(false)
                 .conflicts_with("number_lines"),
         )
+        .arg(
+            Arg::with_name("show_all")
+                .short("A")
+                .long("show-all")
+                .help("Equivalent to -vET")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_ends")
+                .short("E")
+                .long("show-ends")
+                .help("Display $ at the end of each line")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_tabs")
+                .short("T")
+                .long("show-tabs")
+                .help("Display TAB characters as ^I")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_nonprinting")
+                .short("v")
+                .long("show-nonprinting")
+                .help("Use ^ and M- notation, except for LFD and TAB")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("squeeze_blank")
+                .short("s")
+                .long("squeeze-blank")
+                .help("Suppress repeated empty output lines")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("number_start")
+                .long("number-start")
+                .value_name("NUMBER")
+                .help("First line number [default: 1]")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("number_increment")
+                .long("number-increment")
+                .value_name("NUMBER")
+                .help("Line number increment [default: 1]")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("number_width")
+                .long("number-width")
+                .value_name("NUMBER")
+                .help("Columns for line numbers [default: 6]"),
+        )
+        .arg(
+            Arg::with_name("number_separator")
+                .long("number-separator")
+                .value_name("STRING")
+                .help("Text after each line number [default: TAB]"),
+        )
+        .arg(
+            Arg::with_name("number_format")
+                .long("number-format")
+                .value_name("FORMAT")
+                .help("Line number format: ln, rn or rz [default: rn]")
+                .possible_values(&["ln", "rn", "rz"]),
+        )
+        .arg(
+            Arg::with_name("section_delimiter")
+                .long("section-delimiter")
+                .value_name("STRING")
+                .help("Restart numbering after lines equal to STRING, which are not printed")
+                .allow_hyphen_values(true),
+        )
         .get_matches();
+    let show_all = matches.is_present("show_all");
+    let number_start = matches
+        .value_of("number_start")
+        .map(parse_int)
+        .transpose()
+        .map_err(|e| format!("illegal start number -- {}", e))?;
+    let number_increment = matches
+        .value_of("number_increment")
+        .map(parse_int)
+        .transpose()
+        .map_err(|e| format!("illegal increment -- {}", e))?;
+    let number_width = matches
+        .value_of("number_width")
+        .map(parse_positive_int)
+        .transpose()
+        .map_err(|e| format!("illegal width -- {}", e))?;
+    let number_format = match matches.value_of("number_format") {
+        Some("ln") => NumberFormat::LeftJustified,
+        Some("rz") => NumberFormat::RightZeroPadded,
+        _ => NumberFormat::RightJustified,
+    };
+    // The numbering options imply -n unless -b was given
+    let numbering = [
+        "number_start",
+        "number_increment",
+        "number_width",
+        "number_separator",
+        "number_format",
+        "section_delimiter",
+    ]
+    .iter()
+    .any(|name| matches.is_present(name));
+    let number_nonblank_lines = matches.is_present("number_nonblank_lines");
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
-        number_lines: matches.is_present("number_lines"),
-        number_nonblank_lines: matches.is_present("number_nonblank_lines"),
+        number_lines: matches.is_present("number_lines") || (numbering && !number_nonblank_lines),
+        number_nonblank_lines,
+        show_ends: show_all || matches.is_present("show_ends"),
+        show_tabs: show_all || matches.is_present("show_tabs"),
+        show_nonprinting: show_all || matches.is_present("show_nonprinting"),
+        squeeze_blank: matches.is_present("squeeze_blank"),
+        number_start: number_start.unwrap_or(1),
+        number_increment: number_increment.unwrap_or(1),
+        number_width: number_width.unwrap_or(6),
+        number_separator: matches
+            .value_of("number_separator")
+            .unwrap_or("\t")
+            .to_string(),
+        number_format,
+        section_delimiter: matches.value_of("section_delimiter").map(String::from),
     })
 }
 
+fn parse_int(val: &str) -> MyResult<i64> {
+    val.parse().map_err(|_| From::from(val))
+}
+
+fn parse_positive_int(val: &str) -> MyResult<usize> {
+    match val.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(From::from(val)),
+    }
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
         _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
     }
 }
-
@@ -1,6 +1,8 @@
 fn main() {
     if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprint!("{}", e);
+        if !e.is::<catr::AlreadyReported>() {
+            eprint!("{}", e);
+        }
         std::process::exit(1);
     }
 }
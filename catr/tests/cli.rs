@@ -11,6 +11,7 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const SPECIAL: &str = "tests/inputs/special.txt";
 
 // --------------------------------------------------
 #[test]
@@ -47,14 +48,14 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
@@ -69,8 +70,8 @@ fn run_stdin(
     args: &[&str],
     expected_file: &str,
 ) -> TestResult {
-    let input = fs::read_to_string(input_file)?;
-    let expected = fs::read_to_string(expected_file)?;
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .write_stdin(input)
@@ -198,3 +199,197 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn special_show_all() -> TestResult {
+    run(&["-A", SPECIAL], "tests/expected/special.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_ends() -> TestResult {
+    run(&["-E", SPECIAL], "tests/expected/special.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_tabs() -> TestResult {
+    run(&["-T", SPECIAL], "tests/expected/special.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_nonprinting() -> TestResult {
+    run(&["-v", SPECIAL], "tests/expected/special.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_squeeze_blank() -> TestResult {
+    run(&["-s", SPECIAL], "tests/expected/special.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_ns() -> TestResult {
+    run(&["-n", "-s", SPECIAL], "tests/expected/special.txt.ns.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_stdin_ba() -> TestResult {
+    run_stdin(SPECIAL, &["-b", "-A"], "tests/expected/special.txt.bA.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_passthrough() -> TestResult {
+    let input: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+    Command::cargo_bin(PRG)?
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn special_plain() -> TestResult {
+    run(&[SPECIAL, "-", SPECIAL], "tests/expected/special.txt.3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn numbering_continues_without_newline() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "-", FOX])
+        .write_stdin("one\ntwo")
+        .assert()
+        .success()
+        .stdout(
+            "     1\tone\n     2\ttwoThe quick brown fox jumps over the lazy dog.\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_format() -> TestResult {
+    run(
+        &[
+            "--number-start",
+            "5",
+            "--number-increment",
+            "2",
+            "--number-width",
+            "3",
+            "--number-separator",
+            ": ",
+            "--number-format",
+            "rz",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.nl.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_left() -> TestResult {
+    run(
+        &["--number-format", "ln", BUSTLE],
+        "tests/expected/the-bustle.txt.ln.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--number-width", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal width -- 0"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sections() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-b", "--section-delimiter", "---", "tests/inputs/sections.txt"])
+        .assert()
+        .success()
+        .stdout("     1\ta\n     2\tb\n     1\tc\n\n     2\td\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reports_every_bad_file() -> TestResult {
+    let bad1 = gen_bad_file();
+    let bad2 = gen_bad_file();
+    for flags in [&[][..], &["-n"][..]] {
+        let output = Command::cargo_bin(PRG)?
+            .args(flags)
+            .args([bad1.as_str(), FOX, "tests/inputs", bad2.as_str()])
+            .output()?;
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(stderr.contains(&format!("Failed to open {}", bad1)));
+        assert!(stderr.contains("Failed to read tests/inputs: Is a directory"));
+        assert!(stderr.contains(&format!("Failed to open {}", bad2)));
+        // Nothing is reported twice
+        assert_eq!(stderr.lines().count(), 3);
+        assert!(String::from_utf8(output.stdout)?.contains("The quick brown fox"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_on_broken_pipe() -> TestResult {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let path = std::env::temp_dir().join(format!("catr-{}", gen_bad_file()));
+    fs::write(&path, vec![b'x'; 1 << 24])?;
+    for flags in [&[][..], &["-n"][..]] {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .args(flags)
+            .arg(&path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut buf = [0; 10];
+        child.stdout.take().unwrap().read_exact(&mut buf)?;
+        let output = child.wait_with_output()?;
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+    }
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stops_on_write_error() -> TestResult {
+    let path = std::env::temp_dir().join(format!("catr-{}", gen_bad_file()));
+    fs::write(&path, vec![b'x'; 1 << 24])?;
+    for flags in [&[][..], &["-n"][..]] {
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .args(flags)
+            .arg(&path)
+            .arg(&path)
+            .stdout(fs::File::create("/dev/full")?)
+            .output()?;
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr)?;
+        assert_eq!(stderr.lines().count(), 1);
+        assert!(stderr.starts_with("write error: "));
+    }
+    fs::remove_file(&path)?;
+    Ok(())
+}
@@ -1,13 +1,23 @@
 use clap::{App, Arg};
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+#[derive(Debug, PartialEq)]
+pub enum Count {
+    First(usize),
+    AllButLast(usize),
+}
 
 #[derive(Debug)]
 pub struct Config {
     pub files: Vec<String>,
-    pub lines: usize,
-    pub bytes: Option<usize>,
+    pub lines: Count,
+    pub bytes: Option<Count>,
+    pub quiet: bool,
+    pub verbose: bool,
+    pub separator: u8,
 }
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
@@ -29,7 +39,8 @@ pub fn get_args() -> MyResult<Config> {
                 .short("n")
                 .long("lines")
                 .value_name("LINES")
-                .help("Number of lines")
+                .help("Number of lines, or all but the last -N")
+                .allow_hyphen_values(true)
                 .default_value("10"),
         )
         .arg(
@@ -37,26 +48,55 @@ pub fn get_args() -> MyResult<Config> {
                 .short("c")
                 .long("bytes")
                 .value_name("BYTES")
-                .help("Number of bytes")
+                .help("Number of bytes, or all but the last -N")
+                .allow_hyphen_values(true)
                 .conflicts_with("lines"),
         )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .alias("silent")
+                .help("Never print headers")
+                .overrides_with("verbose"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("Always print headers")
+                .overrides_with("quiet"),
+        )
+        .arg(
+            Arg::with_name("zero_terminated")
+                .short("z")
+                .long("zero-terminated")
+                .help("Line delimiter is NUL, not newline"),
+        )
         .get_matches();
     // my solution, horrible non idiomatic
     // let lines = parse_positive_int(matches.value_of("lines").unwrap()).unwrap();
     let lines = matches
         .value_of("lines")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal line count -- {}", e))?;
     let bytes = matches
         .value_of("bytes")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal byte count -- {}", e))?;
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
         lines: lines.unwrap(),
         bytes,
+        quiet: matches.is_present("quiet"),
+        verbose: matches.is_present("verbose"),
+        separator: if matches.is_present("zero_terminated") {
+            0
+        } else {
+            b'\n'
+        },
     })
 }
 
@@ -64,34 +104,27 @@ pub fn run(config: Config) -> MyResult<()> {
     let num_files = config.files.len();
 
     for (file_num, filename) in config.files.iter().enumerate() {
-        match open(&filename) {
+        match open(filename) {
             Err(err) => eprintln!("head: {}: {}", filename, err),
-            Ok(mut file) => {
-                if num_files > 1 {
-                    println!(
-                        "{}==> {} <==",
-                        if file_num > 0 { "\n" } else { "" },
-                        filename
-                    )
+            Ok(file) => {
+                if config.verbose || (!config.quiet && num_files > 1) {
+                    let name = match filename.as_str() {
+                        "-" => "standard input",
+                        name => name,
+                    };
+                    println!("{}==> {} <==", if file_num > 0 { "\n" } else { "" }, name)
                 }
-                match config.bytes {
-                    Some(limit) => {
-                        let mut handle = file.take(limit as u64);
-                        let mut buffer = vec![0; limit];
-                        let bytes_read = handle.read(&mut buffer)?;
-                        print!("{}", String::from_utf8_lossy(&buffer[..bytes_read]));
+                match &config.bytes {
+                    Some(Count::First(limit)) => {
+                        io::copy(&mut file.take(*limit as u64), &mut io::stdout())?;
                     }
-                    None => {
-                        let mut line = String::new();
-                        for _ in 0..config.lines {
-                            let bytes = file.read_line(&mut line)?;
-                            if bytes == 0 {
-                                break;
-                            }
-                            print!("{}", line);
-                            line.clear();
+                    Some(Count::AllButLast(num)) => print_all_but_last_bytes(file, *num)?,
+                    None => match config.lines {
+                        Count::First(num) => print_lines(file, num, config.separator)?,
+                        Count::AllButLast(num) => {
+                            print_all_but_last_lines(file, num, config.separator)?
                         }
-                    }
+                    },
                 }
             }
         }
@@ -99,6 +132,111 @@ pub fn run(config: Config) -> MyResult<()> {
     Ok(())
 }
 
+// Lines are read as raw bytes up to and including `separator`, so line
+// endings and invalid UTF-8 pass through unchanged
+fn print_lines(mut file: impl BufRead, num: usize, separator: u8) -> MyResult<()> {
+    let mut out = io::stdout();
+    let mut line = Vec::new();
+    for _ in 0..num {
+        if file.read_until(separator, &mut line)? == 0 {
+            break;
+        }
+        out.write_all(&line)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+// Holds back the last `num` lines, printing each line once it is
+// known not to be one of them
+fn print_all_but_last_lines(mut file: impl BufRead, num: usize, separator: u8) -> MyResult<()> {
+    let mut out = io::stdout();
+    let mut last = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if file.read_until(separator, &mut line)? == 0 {
+            return Ok(());
+        }
+        last.push_back(line);
+        if last.len() > num {
+            out.write_all(&last.pop_front().unwrap_or_default())?;
+        }
+    }
+}
+
+// Like `print_all_but_last_lines`, holding back the last `num` bytes
+fn print_all_but_last_bytes(mut file: impl BufRead, num: usize) -> MyResult<()> {
+    let mut out = io::stdout();
+    let mut last = VecDeque::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            return Ok(());
+        }
+        last.extend(&buffer[..bytes_read]);
+        let excess = last.len().saturating_sub(num);
+        if excess > 0 {
+            let (front, back) = last.as_slices();
+            let front_len = front.len().min(excess);
+            out.write_all(&front[..front_len])?;
+            out.write_all(&back[..excess - front_len])?;
+            last.drain(..excess);
+        }
+    }
+}
+
+// A leading "-" counts from the end
+pub fn parse_count(val: &str) -> MyResult<Count> {
+    match val.strip_prefix('-') {
+        // All but the last 0 is everything, which GNU head allows
+        Some(num) => parse_multiple(num)
+            .map(Count::AllButLast)
+            .map_err(|_| From::from(val)),
+        None => parse_size(val).map(Count::First),
+    }
+}
+
+const SIZE_SUFFIXES: [(&str, usize); 11] = [
+    ("b", 512),
+    ("K", 1 << 10),
+    ("KiB", 1 << 10),
+    ("kB", 1000),
+    ("KB", 1000),
+    ("M", 1 << 20),
+    ("MiB", 1 << 20),
+    ("MB", 1_000_000),
+    ("G", 1 << 30),
+    ("GiB", 1 << 30),
+    ("GB", 1_000_000_000),
+];
+
+// A positive integer with an optional multiplier suffix such as K or MiB
+pub fn parse_size(val: &str) -> MyResult<usize> {
+    match parse_multiple(val)? {
+        0 => Err(From::from(val)),
+        size => Ok(size),
+    }
+}
+
+// Like parse_size, but zero is allowed
+fn parse_multiple(val: &str) -> MyResult<usize> {
+    let split = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    let (num, suffix) = val.split_at(split);
+    let multiplier = match suffix {
+        "" => 1,
+        _ => SIZE_SUFFIXES
+            .iter()
+            .find(|(name, _)| *name == suffix)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| val.to_string())?,
+    };
+    num.parse::<usize>()
+        .ok()
+        .and_then(|num| num.checked_mul(multiplier))
+        .ok_or_else(|| From::from(val))
+}
+
 pub fn parse_positive_int(val: &str) -> MyResult<usize> {
     match val.parse::<usize>() {
         Ok(n) if n > 0 => Ok(n),
@@ -127,3 +265,28 @@ fn test_parse_positive_int() {
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(), "0".to_string());
 }
+
+#[test]
+fn test_parse_count() {
+    assert_eq!(parse_count("3").unwrap(), Count::First(3));
+    assert_eq!(parse_count("-3").unwrap(), Count::AllButLast(3));
+    assert_eq!(parse_count("-0").unwrap(), Count::AllButLast(0));
+    assert_eq!(parse_count("-0K").unwrap(), Count::AllButLast(0));
+    assert_eq!(parse_count("--3").unwrap_err().to_string(), "--3");
+    assert_eq!(parse_count("-foo").unwrap_err().to_string(), "-foo");
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("3").unwrap(), 3);
+    assert_eq!(parse_size("2b").unwrap(), 1024);
+    assert_eq!(parse_size("2K").unwrap(), 2048);
+    assert_eq!(parse_size("2KiB").unwrap(), 2048);
+    assert_eq!(parse_size("2kB").unwrap(), 2000);
+    assert_eq!(parse_size("1M").unwrap(), 1 << 20);
+    assert_eq!(parse_size("1GB").unwrap(), 1_000_000_000);
+    assert_eq!(parse_size("K").unwrap_err().to_string(), "K");
+    assert_eq!(parse_size("0K").unwrap_err().to_string(), "0K");
+    assert_eq!(parse_size("3X").unwrap_err().to_string(), "3X");
+    assert!(parse_size(&format!("{}G", usize::MAX)).is_err());
+}
@@ -1,11 +1,7 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
+use std::{error::Error, fs};
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -93,17 +89,12 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
@@ -114,18 +105,13 @@ fn run_stdin(
     input_file: &str,
     expected_file: &str,
 ) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-    let input = fs::read_to_string(input_file)?;
-
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
@@ -419,3 +405,155 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn ten_n_minus_2() -> TestResult {
+    run(&[TEN, "-n", "-2"], "tests/expected/ten.txt.n-2.out")
+}
+
+#[test]
+fn ten_n_minus_2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], TEN, "tests/expected/ten.txt.n-2.out")
+}
+
+#[test]
+fn three_n_minus_2() -> TestResult {
+    run(&[THREE, "-n", "-2"], "tests/expected/three.txt.n-2.out")
+}
+
+#[test]
+fn one_c_minus_4() -> TestResult {
+    run(&[ONE, "-c", "-4"], "tests/expected/one.txt.c-4.out")
+}
+
+#[test]
+fn ten_c_minus_4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], TEN, "tests/expected/ten.txt.c-4.out")
+}
+
+#[test]
+fn ten_n_minus_0() -> TestResult {
+    run(&[TEN, "-n", "-0"], TEN)
+}
+
+#[test]
+fn ten_c_minus_0_stdin() -> TestResult {
+    run_stdin(&["-c", "-0"], TEN, TEN)
+}
+
+#[test]
+fn multiple_files_n_minus_2() -> TestResult {
+    run(
+        &["-n", "-2", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_minus_4() -> TestResult {
+    run(
+        &["-c", "-4", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c-4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_suffix() -> TestResult {
+    run(&[TEN, "-c", "1K"], "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_n_suffix() -> TestResult {
+    run(&[TEN, "-n", "1KiB"], "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn binary_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-c", "3"])
+        .write_stdin(&b"\xff\x00\xc3\x96rest"[..])
+        .assert()
+        .success()
+        .stdout(&b"\xff\x00\xc3"[..]);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_quiet() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-q", "-n", "1", ONE, TWO])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string(ONE)? + "Two lines.\n");
+
+    Ok(())
+}
+
+#[test]
+fn one_file_verbose() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-v", ONE])
+        .assert()
+        .success()
+        .stdout(format!("==> {} <==\n{}", ONE, fs::read_to_string(ONE)?));
+
+    Command::cargo_bin(PRG)?
+        .arg("-v")
+        .write_stdin(fs::read_to_string(ONE)?)
+        .assert()
+        .success()
+        .stdout(format!("==> standard input <==\n{}", fs::read_to_string(ONE)?));
+
+    Ok(())
+}
+
+#[test]
+fn last_of_quiet_and_verbose_wins() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-q", "-v", ONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("==> "));
+
+    Ok(())
+}
+
+#[test]
+fn zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-z", "-n", "2"])
+        .write_stdin("a\nb\0c\0d\0")
+        .assert()
+        .success()
+        .stdout("a\nb\0c\0");
+
+    Ok(())
+}
+
+#[test]
+fn keeps_crlf_and_invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-n", "2"])
+        .write_stdin(&b"one\r\n\xff\xfe\r\nthree\r\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"one\r\n\xff\xfe\r\n"[..]);
+
+    Ok(())
+}
+
+#[test]
+fn all_but_last_invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-n", "-1"])
+        .write_stdin(&b"\xff\n\xfe\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"\xff\n"[..]);
+
+    Ok(())
+}
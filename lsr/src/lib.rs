@@ -1,15 +1,86 @@
-use chrono::{DateTime, Utc};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Duration, Local,
+};
 use clap::{App, Arg};
-use std::{error::Error, fs, os::unix::prelude::MetadataExt, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    error::Error,
+    ffi::CString,
+    fs::{self, FileType, Metadata},
+    os::unix::prelude::{FileTypeExt, MetadataExt, OsStrExt},
+    path::{Path, PathBuf},
+};
 use tabular::{Row, Table};
+use unicode_width::UnicodeWidthStr;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Time,
+    Size,
+    Extension,
+    Version,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    OneLine,
+    Columns,
+    Across,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeStyle {
+    Locale,
+    Iso,
+    LongIso,
+    FullIso,
+    Format { old: String, recent: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeStyle {
+    Bytes,
+    Human,
+    Si,
+}
+
+// The type-based part of the default dircolors database, used when
+// LS_COLORS isn't set
+const DEFAULT_LS_COLORS: &str = "rs=0:di=01;34:ln=01;36:pi=40;33:so=01;35:\
+bd=40;33;01:cd=40;33;01:or=40;31;01:su=37;41:sg=30;43:tw=30;42:ow=34;42:st=37;44:ex=01;32";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LsColors {
+    types: HashMap<String, String>,
+    extensions: Vec<(String, String)>,
+}
+
 #[derive(Debug)]
 pub struct Config {
     paths: Vec<String>,
     long: bool,
     show_hidden: bool,
+    sort: SortKey,
+    reverse: bool,
+    group_dirs_first: bool,
+    layout: Layout,
+    width: usize,
+    time_style: TimeStyle,
+    size_style: SizeStyle,
+    blocks: bool,
+    numeric_ids: bool,
+    colors: Option<LsColors>,
+    classify: bool,
+    tree: bool,
+    max_depth: Option<usize>,
+    ascii: bool,
+    inode: bool,
+    xattr: bool,
 }
 
 pub fn get_args() -> MyResult<Config> {
@@ -31,6 +102,165 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Long listing")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("time")
+                .short("t")
+                .help("Sort by modification time, newest first")
+                .takes_value(false)
+                .conflicts_with_all(&["size", "extension", "version"]),
+        )
+        .arg(
+            Arg::with_name("size")
+                .short("S")
+                .help("Sort by file size, largest first")
+                .takes_value(false)
+                .conflicts_with_all(&["time", "extension", "version"]),
+        )
+        .arg(
+            Arg::with_name("extension")
+                .short("X")
+                .help("Sort alphabetically by entry extension")
+                .takes_value(false)
+                .conflicts_with_all(&["time", "size", "version"]),
+        )
+        .arg(
+            Arg::with_name("version")
+                .short("v")
+                .help("Natural sort of (version) numbers within names")
+                .takes_value(false)
+                .conflicts_with_all(&["time", "size", "extension"]),
+        )
+        .arg(
+            Arg::with_name("reverse")
+                .short("r")
+                .long("reverse")
+                .help("Reverse order while sorting")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("group_dirs_first")
+                .long("group-directories-first")
+                .help("Group directories before files")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("one_line")
+                .short("1")
+                .help("List one entry per line")
+                .takes_value(false)
+                .conflicts_with_all(&["columns", "across"]),
+        )
+        .arg(
+            Arg::with_name("columns")
+                .short("C")
+                .help("List entries by columns")
+                .takes_value(false)
+                .conflicts_with_all(&["one_line", "across"]),
+        )
+        .arg(
+            Arg::with_name("across")
+                .short("x")
+                .help("List entries by lines instead of by columns")
+                .takes_value(false)
+                .conflicts_with_all(&["one_line", "columns"]),
+        )
+        .arg(
+            Arg::with_name("width")
+                .short("w")
+                .long("width")
+                .value_name("COLS")
+                .help("Set output width to COLS"),
+        )
+        .arg(
+            Arg::with_name("time_style")
+                .long("time-style")
+                .value_name("STYLE")
+                .help("Time format: full-iso, long-iso, iso, locale or +FORMAT")
+                .default_value("locale"),
+        )
+        .arg(
+            Arg::with_name("human")
+                .short("h")
+                .long("human-readable")
+                .help("Print sizes like 1K 234M 2G")
+                .takes_value(false)
+                .conflicts_with("si"),
+        )
+        .arg(
+            Arg::with_name("si")
+                .long("si")
+                .help("Like -h, but use powers of 1000 not 1024")
+                .takes_value(false)
+                .conflicts_with("human"),
+        )
+        .arg(
+            Arg::with_name("blocks")
+                .short("s")
+                .long("size")
+                .help("Print the allocated size of each file, in blocks")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("numeric")
+                .short("n")
+                .long("numeric-uid-gid")
+                .help("Like -l, but list numeric user and group IDs")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .value_name("WHEN")
+                .help("Colorize the output")
+                .possible_values(&["auto", "always", "never"])
+                .default_value("never"),
+        )
+        .arg(
+            Arg::with_name("classify")
+                .short("F")
+                .long("classify")
+                .help("Append indicator (one of */=@|) to entries")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("tree")
+                .long("tree")
+                .help("List subdirectories recursively as a tree")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("level")
+                .short("L")
+                .long("level")
+                .value_name("DEPTH")
+                .help("Descend at most DEPTH directories in tree mode"),
+        )
+        .arg(
+            Arg::with_name("dirs_first")
+                .long("dirs-first")
+                .help("Same as --group-directories-first")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("charset")
+                .long("charset")
+                .value_name("CHARSET")
+                .help("Characters for the tree lines [default: from locale]")
+                .possible_values(&["utf-8", "ascii"]),
+        )
+        .arg(
+            Arg::with_name("inode")
+                .short("i")
+                .long("inode")
+                .help("Print the index number of each file")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("xattr")
+                .long("xattr")
+                .help("List extended attributes under each entry, one entry per line")
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("files")
                 .value_name("PATH")
@@ -40,35 +270,656 @@ pub fn get_args() -> MyResult<Config> {
         )
         .get_matches();
     let paths = matches.values_of_lossy("files");
-    let long = matches.is_present("long");
+    let numeric_ids = matches.is_present("numeric");
+    let long = matches.is_present("long") || numeric_ids;
     let show_hidden = matches.is_present("all");
+    let sort = if matches.is_present("time") {
+        SortKey::Time
+    } else if matches.is_present("size") {
+        SortKey::Size
+    } else if matches.is_present("extension") {
+        SortKey::Extension
+    } else if matches.is_present("version") {
+        SortKey::Version
+    } else {
+        SortKey::Name
+    };
+    let layout = if matches.is_present("one_line") {
+        Layout::OneLine
+    } else if matches.is_present("columns") {
+        Layout::Columns
+    } else if matches.is_present("across") {
+        Layout::Across
+    } else if stdout_is_tty() {
+        Layout::Columns
+    } else {
+        Layout::OneLine
+    };
+    let width = match matches.value_of("width") {
+        Some(val) => val
+            .parse::<usize>()
+            .map_err(|_| format!("illegal width -- {}", val))?,
+        None => terminal_width(),
+    };
+    let time_style = matches
+        .value_of("time_style")
+        .map(parse_time_style)
+        .transpose()
+        .map_err(|e| format!("invalid time style -- {}", e))?;
+
+    let size_style = if matches.is_present("human") {
+        SizeStyle::Human
+    } else if matches.is_present("si") {
+        SizeStyle::Si
+    } else {
+        SizeStyle::Bytes
+    };
+
+    let colors = match matches.value_of("color") {
+        Some("always") => true,
+        Some("auto") => stdout_is_tty(),
+        _ => false,
+    }
+    .then(|| {
+        let spec = std::env::var("LS_COLORS").unwrap_or_default();
+        LsColors::parse(if spec.is_empty() {
+            DEFAULT_LS_COLORS
+        } else {
+            &spec
+        })
+    });
+
+    let max_depth = matches
+        .value_of("level")
+        .map(parse_positive_int)
+        .transpose()
+        .map_err(|e| format!("illegal level -- {}", e))?;
+    let ascii = match matches.value_of("charset") {
+        Some(charset) => charset == "ascii",
+        None => !locale_is_utf8(),
+    };
 
     Ok(Config {
         paths: paths.unwrap(),
         long,
         show_hidden,
+        sort,
+        reverse: matches.is_present("reverse"),
+        group_dirs_first: matches.is_present("group_dirs_first")
+            || matches.is_present("dirs_first"),
+        layout,
+        width,
+        time_style: time_style.unwrap(),
+        size_style,
+        blocks: matches.is_present("blocks"),
+        numeric_ids,
+        colors,
+        classify: matches.is_present("classify"),
+        tree: matches.is_present("tree"),
+        max_depth,
+        ascii,
+        inode: matches.is_present("inode"),
+        xattr: matches.is_present("xattr"),
     })
 }
 
+pub fn parse_positive_int(val: &str) -> MyResult<usize> {
+    match val.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(From::from(val)),
+    }
+}
+
+// Follows the usual precedence of the locale variables
+fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|val| !val.is_empty())
+        .map(|val| {
+            let val = val.to_lowercase();
+            val.contains("utf-8") || val.contains("utf8")
+        })
+        .unwrap_or(false)
+}
+
 pub fn run(config: Config) -> MyResult<()> {
-    let paths = find_files(&config.paths, config.show_hidden)?;
+    if config.tree {
+        return run_tree(&config);
+    }
+    // Like ls, list file operands together first and then each directory
+    // on its own, so that every directory gets its own total
+    let (dirs, files): (Vec<String>, Vec<String>) = config
+        .paths
+        .iter()
+        .cloned()
+        .partition(|path| fs::metadata(path).map(|m| m.is_dir()).unwrap_or(false));
+    let show_headers = config.paths.len() > 1;
+    let mut first = true;
+    let files = find_files(&files, config.show_hidden)?;
+    if !files.is_empty() {
+        print_entries(files, &config, false)?;
+        first = false;
+    }
+    let dirs = sort_paths(
+        dirs.iter().map(PathBuf::from).collect(),
+        config.sort,
+        config.reverse,
+        false,
+    );
+    for dir in dirs {
+        if !first {
+            println!();
+        }
+        if show_headers {
+            println!("{}:", dir.display());
+        }
+        let entries = find_files(&[dir.display().to_string()], config.show_hidden)?;
+        print_entries(entries, &config, true)?;
+        first = false;
+    }
+    Ok(())
+}
+
+fn run_tree(config: &Config) -> MyResult<()> {
+    let mut counts = (0, 0);
+    for path in &config.paths {
+        let root = PathBuf::from(path);
+        let metadata = match fs::symlink_metadata(&root) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                continue;
+            }
+        };
+        let mut rows = vec![(
+            root.clone(),
+            format_name(&root, path, &metadata, config, false),
+        )];
+        if root.is_dir() {
+            if let Err(e) = walk_tree(&root, "", 1, config, &mut rows, &mut counts) {
+                eprintln!("{}: {}", path, e);
+            }
+        } else {
+            counts.1 += 1;
+        }
+        if config.long {
+            print!("{}", format_table(&rows, config)?);
+        } else {
+            for (path, name) in rows {
+                println!("{}", name);
+                if config.xattr {
+                    for attr in format_xattrs(&path) {
+                        println!("{}", attr);
+                    }
+                }
+            }
+        }
+    }
+    let (dirs, files) = counts;
+    println!(
+        "\n{} director{}, {} file{}",
+        dirs,
+        if dirs == 1 { "y" } else { "ies" },
+        files,
+        if files == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+// Adds a row for each entry under `dir`, drawing the connectors after the
+// `prefix` inherited from the parent directories. `counts` tallies the
+// directories and files shown.
+fn walk_tree(
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    config: &Config,
+    rows: &mut Vec<(PathBuf, String)>,
+    counts: &mut (usize, usize),
+) -> MyResult<()> {
+    let (tee, elbow, pipe) = if config.ascii {
+        ("|-- ", "`-- ", "|   ")
+    } else {
+        ("├── ", "└── ", "│   ")
+    };
+    let mut children = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if config.show_hidden || !entry.file_name().to_string_lossy().starts_with('.') {
+            children.push(entry.path());
+        }
+    }
+    let children = sort_paths(
+        children,
+        config.sort,
+        config.reverse,
+        config.group_dirs_first,
+    );
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let metadata = match fs::symlink_metadata(child) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("{}: {}", child.display(), e);
+                continue;
+            }
+        };
+        let text = child.file_name().unwrap_or_default().to_string_lossy();
+        let name = format_name(child, &text, &metadata, config, false);
+        rows.push((
+            child.clone(),
+            format!("{}{}{}", prefix, if last { elbow } else { tee }, name),
+        ));
+        if !metadata.is_dir() {
+            counts.1 += 1;
+            continue;
+        }
+        counts.0 += 1;
+        if config.max_depth.is_none_or(|max| depth < max) {
+            let row = rows.len() - 1;
+            let prefix = format!("{}{}", prefix, if last { "    " } else { pipe });
+            if walk_tree(child, &prefix, depth + 1, config, rows, counts).is_err() {
+                rows[row].1.push_str("  [error opening dir]");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_entries(paths: Vec<PathBuf>, config: &Config, is_dir: bool) -> MyResult<()> {
+    let paths = sort_paths(paths, config.sort, config.reverse, config.group_dirs_first);
+    if is_dir && (config.long || config.blocks) {
+        let total = paths
+            .iter()
+            .filter_map(|path| fs::symlink_metadata(path).ok())
+            .map(|metadata| metadata.blocks())
+            .sum();
+        println!("total {}", format_blocks(total, config.size_style));
+    }
     if config.long {
-        println!("{}", format_output(&paths)?);
+        print!("{}", format_output(&paths, config)?);
     } else {
-        for path in paths {
-            println!("{}", path.display());
+        let mut names: Vec<String> = paths
+            .iter()
+            .map(|path| match fs::symlink_metadata(path) {
+                Ok(metadata) => {
+                    format_name(path, &path.display().to_string(), &metadata, config, true)
+                }
+                Err(_) => path.display().to_string(),
+            })
+            .collect();
+        if config.blocks {
+            let blocks = paths
+                .iter()
+                .map(|path| {
+                    let blocks = fs::symlink_metadata(path).map_or(0, |m| m.blocks());
+                    format_blocks(blocks, config.size_style)
+                })
+                .collect();
+            names = prefix_column(names, blocks);
+        }
+        if config.inode {
+            let inodes = paths
+                .iter()
+                .map(|path| {
+                    fs::symlink_metadata(path)
+                        .map_or(0, |m| m.ino())
+                        .to_string()
+                })
+                .collect();
+            names = prefix_column(names, inodes);
+        }
+        match config.layout {
+            // Attributes go under their entry, so they need a line each
+            _ if config.xattr => {
+                for (path, name) in paths.iter().zip(names) {
+                    println!("{}", name);
+                    for line in format_xattrs(path) {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Layout::OneLine => {
+                for name in names {
+                    println!("{}", name);
+                }
+            }
+            Layout::Columns => print!("{}", format_grid(&names, config.width, false)),
+            Layout::Across => print!("{}", format_grid(&names, config.width, true)),
         }
     }
     Ok(())
 }
 
+// Right-aligns `column` and puts it in front of each name
+fn prefix_column(names: Vec<String>, column: Vec<String>) -> Vec<String> {
+    let width = column.iter().map(|c| c.len()).max().unwrap_or(0);
+    column
+        .iter()
+        .zip(names)
+        .map(|(c, name)| format!("{:>width$} {}", c, name, width = width))
+        .collect()
+}
+
+// Reads the NUL-separated names from llistxattr, without following links
+#[cfg(target_os = "linux")]
+fn list_xattrs(path: &Path) -> Vec<String> {
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return vec![],
+    };
+    let size = unsafe { libc::llistxattr(path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return vec![];
+    }
+    let mut buf = vec![0u8; size as usize];
+    let size = unsafe {
+        libc::llistxattr(
+            path.as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+        )
+    };
+    if size <= 0 {
+        return vec![];
+    }
+    buf.truncate(size as usize);
+    buf.split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn get_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let name = CString::new(name).ok()?;
+    let size = unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return None;
+    }
+    let mut buf = vec![0u8; size as usize];
+    let size = unsafe {
+        libc::lgetxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len(),
+        )
+    };
+    if size < 0 {
+        return None;
+    }
+    buf.truncate(size as usize);
+    Some(buf)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_xattrs(_path: &Path) -> Vec<String> {
+    vec![]
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_xattr(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+    None
+}
+
+// "+" marks a POSIX ACL and "@" any other extended attribute. SELinux labels
+// are on nearly every file, so they don't count.
+fn xattr_indicator(path: &Path) -> &'static str {
+    let names = list_xattrs(path);
+    if names
+        .iter()
+        .any(|name| name == "system.posix_acl_access" || name == "system.posix_acl_default")
+    {
+        "+"
+    } else if names.iter().any(|name| name != "security.selinux") {
+        "@"
+    } else {
+        ""
+    }
+}
+
+fn format_xattrs(path: &Path) -> Vec<String> {
+    list_xattrs(path)
+        .into_iter()
+        .map(|name| {
+            let value = get_xattr(path, &name).unwrap_or_default();
+            format!("\t{}: {:?}", name, String::from_utf8_lossy(&value))
+        })
+        .collect()
+}
+
+impl LsColors {
+    // Parses the colon-separated "key=SGR" list used by LS_COLORS, where
+    // keys are either two-letter file types or "*suffix" globs
+    pub fn parse(spec: &str) -> LsColors {
+        let mut colors = LsColors::default();
+        for entry in spec.split(':') {
+            if let Some((key, code)) = entry.split_once('=') {
+                match key.strip_prefix('*') {
+                    Some(suffix) => colors
+                        .extensions
+                        .push((suffix.to_string(), code.to_string())),
+                    None => {
+                        colors.types.insert(key.to_string(), code.to_string());
+                    }
+                }
+            }
+        }
+        colors
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.types
+            .get(key)
+            .map(|code| code.as_str())
+            .filter(|code| !code.is_empty())
+    }
+
+    // Picks the SGR sequence for an entry the way GNU ls does: special
+    // types and permission bits win, then suffixes, then the plain type
+    pub fn style(&self, path: &Path, metadata: &Metadata) -> Option<&str> {
+        let file_type = metadata.file_type();
+        let mode = metadata.mode();
+        if file_type.is_symlink() {
+            if fs::metadata(path).is_err() {
+                if let Some(code) = self.get("or") {
+                    return Some(code);
+                }
+            }
+            self.get("ln")
+        } else if file_type.is_dir() {
+            match (mode & 0o1000 != 0, mode & 0o002 != 0) {
+                (true, true) => self.get("tw"),
+                (false, true) => self.get("ow"),
+                (true, false) => self.get("st"),
+                (false, false) => None,
+            }
+            .or_else(|| self.get("di"))
+        } else if file_type.is_fifo() {
+            self.get("pi")
+        } else if file_type.is_socket() {
+            self.get("so")
+        } else if file_type.is_block_device() {
+            self.get("bd")
+        } else if file_type.is_char_device() {
+            self.get("cd")
+        } else {
+            let special = if mode & 0o4000 != 0 {
+                self.get("su")
+            } else if mode & 0o2000 != 0 {
+                self.get("sg")
+            } else if mode & 0o111 != 0 {
+                self.get("ex")
+            } else {
+                None
+            };
+            special
+                .or_else(|| {
+                    let name = path.file_name()?.as_bytes();
+                    // Later entries override earlier ones
+                    self.extensions
+                        .iter()
+                        .rev()
+                        .find(|(suffix, _)| name.ends_with(suffix.as_bytes()))
+                        .map(|(_, code)| code.as_str())
+                })
+                .or_else(|| self.get("fi"))
+        }
+    }
+
+    fn paint(&self, text: &str, path: &Path, metadata: &Metadata) -> String {
+        match self.style(path, metadata) {
+            Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+fn classify_indicator(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        "/"
+    } else if file_type.is_symlink() {
+        "@"
+    } else if file_type.is_fifo() {
+        "|"
+    } else if file_type.is_socket() {
+        "="
+    } else if file_type.is_file() && metadata.mode() & 0o111 != 0 {
+        "*"
+    } else {
+        ""
+    }
+}
+
+// Renders `text`, the name shown for an entry, with its color and -F
+// indicator. In long mode symlinks also show their target, which is colored
+// and classified in place of the link itself.
+fn format_name(
+    path: &Path,
+    text: &str,
+    metadata: &Metadata,
+    config: &Config,
+    short: bool,
+) -> String {
+    let mut name = match &config.colors {
+        Some(colors) => colors.paint(text, path, metadata),
+        None => text.to_string(),
+    };
+    if !short && metadata.file_type().is_symlink() {
+        if let Ok(target) = fs::read_link(path) {
+            let target_path = path.parent().unwrap_or(Path::new("")).join(&target);
+            name.push_str(" -> ");
+            match fs::metadata(&target_path) {
+                Ok(target_meta) => {
+                    let target_name = target.display().to_string();
+                    match &config.colors {
+                        Some(colors) => {
+                            name.push_str(&colors.paint(&target_name, &target_path, &target_meta))
+                        }
+                        None => name.push_str(&target_name),
+                    }
+                    if config.classify {
+                        name.push_str(classify_indicator(&target_meta));
+                    }
+                }
+                Err(_) => name.push_str(&target.display().to_string()),
+            }
+        }
+    } else if config.classify {
+        name.push_str(classify_indicator(metadata));
+    }
+    name
+}
+
+// The number of terminal columns a string takes, skipping color escapes
+fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        width += rest[..start].width();
+        rest = match rest[start..].find('m') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    width + rest.width()
+}
+
+fn stdout_is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+fn terminal_width() -> usize {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col > 0
+    {
+        return size.ws_col as usize;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|cols| cols.parse().ok())
+        .unwrap_or(80)
+}
+
+// Lays names out in as many columns as fit in `width`, filling each column
+// top to bottom, or each row left to right when `across` is set.
+fn format_grid(names: &[String], width: usize, across: bool) -> String {
+    if names.is_empty() {
+        return String::new();
+    }
+    let widths: Vec<usize> = names.iter().map(|name| display_width(name)).collect();
+    let (mut rows, mut col_widths) = (names.len(), vec![*widths.iter().max().unwrap()]);
+    for cols in (2..=names.len()).rev() {
+        let num_rows = names.len().div_ceil(cols);
+        let num_cols = names.len().div_ceil(num_rows);
+        let mut candidate = vec![0; num_cols];
+        for (i, w) in widths.iter().enumerate() {
+            let col = if across { i % num_cols } else { i / num_rows };
+            candidate[col] = candidate[col].max(*w);
+        }
+        if candidate.iter().sum::<usize>() + 2 * (num_cols - 1) <= width {
+            rows = num_rows;
+            col_widths = candidate;
+            break;
+        }
+    }
+
+    let cols = col_widths.len();
+    let mut out = String::new();
+    for row in 0..rows {
+        for (col, col_width) in col_widths.iter().enumerate() {
+            let i = if across {
+                row * cols + col
+            } else {
+                col * rows + row
+            };
+            if i >= names.len() {
+                break;
+            }
+            out.push_str(&names[i]);
+            let next = if across { i + 1 } else { i + rows };
+            if col + 1 < cols && next < names.len() {
+                out.push_str(&" ".repeat(col_width - widths[i] + 2));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
 pub fn find_files(paths: &[String], show_hidden: bool) -> MyResult<Vec<PathBuf>> {
     let mut res = Vec::new();
     for path in paths {
-        let metadata = fs::metadata(path);
+        // Fall back to the link itself so that dangling symlinks are listed
+        let metadata = fs::metadata(path).or_else(|_| fs::symlink_metadata(path));
         match metadata {
             Ok(meta) => {
-                if meta.is_file() {
+                if !meta.is_dir() {
                     res.push(PathBuf::from(path));
                 } else {
                     for entry in fs::read_dir(path)? {
@@ -90,39 +941,275 @@ pub fn find_files(paths: &[String], show_hidden: bool) -> MyResult<Vec<PathBuf>>
     Ok(res)
 }
 
-fn format_output(paths: &[PathBuf]) -> MyResult<String> {
-    let fmt = "{:<}{:<}  {:>}  {:<}  {:<}  {:>}  {:<}  {:<}";
-    let mut table = Table::new(fmt);
+pub fn sort_paths(
+    paths: Vec<PathBuf>,
+    sort: SortKey,
+    reverse: bool,
+    group_dirs_first: bool,
+) -> Vec<PathBuf> {
+    let mut entries: Vec<(PathBuf, Option<Metadata>)> = paths
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::symlink_metadata(&path).ok();
+            (path, metadata)
+        })
+        .collect();
+    entries.sort_by(|(a, a_meta), (b, b_meta)| {
+        let ord = match sort {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Time => {
+                let a_time = a_meta.as_ref().and_then(|m| m.modified().ok());
+                let b_time = b_meta.as_ref().and_then(|m| m.modified().ok());
+                b_time.cmp(&a_time)
+            }
+            SortKey::Size => {
+                let a_size = a_meta.as_ref().map(|m| m.size());
+                let b_size = b_meta.as_ref().map(|m| m.size());
+                b_size.cmp(&a_size)
+            }
+            SortKey::Extension => extension(a).cmp(extension(b)),
+            SortKey::Version => version_cmp(file_name(a), file_name(b)),
+        }
+        .then_with(|| a.as_os_str().as_bytes().cmp(b.as_os_str().as_bytes()));
+        if reverse {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+    if group_dirs_first {
+        // sort_by_key is stable, so the order within each group is kept
+        entries.sort_by_key(|(path, _)| !path.is_dir());
+    }
+    entries.into_iter().map(|(path, _)| path).collect()
+}
+
+fn file_name(path: &Path) -> &[u8] {
+    path.file_name().unwrap_or(path.as_os_str()).as_bytes()
+}
+
+fn extension(path: &Path) -> &[u8] {
+    path.extension()
+        .map(|ext| ext.as_bytes())
+        .unwrap_or_default()
+}
+
+// Compares runs of digits by numeric value and everything else bytewise,
+// so that "file2" sorts before "file10".
+fn version_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let a_end = i + a[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_end = j + b[j..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let a_num = trim_zeros(&a[i..a_end]);
+            let b_num = trim_zeros(&b[j..b_end]);
+            let ord = a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            i = a_end;
+            j = b_end;
+        } else {
+            if a[i] != b[j] {
+                return a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j)).then_with(|| a.cmp(b))
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+pub fn parse_time_style(val: &str) -> MyResult<TimeStyle> {
+    match val {
+        "locale" => Ok(TimeStyle::Locale),
+        "iso" => Ok(TimeStyle::Iso),
+        "long-iso" => Ok(TimeStyle::LongIso),
+        "full-iso" => Ok(TimeStyle::FullIso),
+        other => match other.strip_prefix('+') {
+            // As with GNU ls, "+OLD\nRECENT" gives separate formats for
+            // old and recent files
+            Some(fmt) if StrftimeItems::new(fmt).all(|item| item != Item::Error) => {
+                let (old, recent) = fmt.split_once('\n').unwrap_or((fmt, fmt));
+                Ok(TimeStyle::Format {
+                    old: old.to_string(),
+                    recent: recent.to_string(),
+                })
+            }
+            _ => Err(From::from(other)),
+        },
+    }
+}
+
+fn format_time(time: &DateTime<Local>, now: &DateTime<Local>, style: &TimeStyle) -> String {
+    // Files modified in the last six months, and not in the future, are
+    // "recent" and get a time of day instead of a year
+    let six_months = Duration::seconds(31_556_952 / 2);
+    let is_recent = *time <= *now && *now - *time < six_months;
+    let fmt = match style {
+        TimeStyle::Locale if is_recent => "%b %e %H:%M",
+        TimeStyle::Locale => "%b %e  %Y",
+        TimeStyle::Iso if is_recent => "%m-%d %H:%M",
+        TimeStyle::Iso => "%Y-%m-%d",
+        TimeStyle::LongIso => "%Y-%m-%d %H:%M",
+        TimeStyle::FullIso => "%Y-%m-%d %H:%M:%S%.9f %z",
+        TimeStyle::Format { recent, .. } if is_recent => recent.as_str(),
+        TimeStyle::Format { old, .. } => old.as_str(),
+    };
+    time.format(fmt).to_string()
+}
+
+fn format_file_type(file_type: &FileType) -> &'static str {
+    if file_type.is_dir() {
+        "d"
+    } else if file_type.is_symlink() {
+        "l"
+    } else if file_type.is_char_device() {
+        "c"
+    } else if file_type.is_block_device() {
+        "b"
+    } else if file_type.is_fifo() {
+        "p"
+    } else if file_type.is_socket() {
+        "s"
+    } else {
+        "-"
+    }
+}
+
+// Sizes are rounded up, as ls does, so that a nonempty file never shows as 0
+fn format_size(size: u64, style: SizeStyle) -> String {
+    let (base, suffixes) = match style {
+        SizeStyle::Bytes => return size.to_string(),
+        SizeStyle::Human => (1024, ["K", "M", "G", "T", "P", "E"]),
+        SizeStyle::Si => (1000, ["k", "M", "G", "T", "P", "E"]),
+    };
+    if size < base {
+        return size.to_string();
+    }
+    let mut value = size as f64 / base as f64;
+    let mut unit = 0;
+    while value >= base as f64 && unit < suffixes.len() - 1 {
+        value /= base as f64;
+        unit += 1;
+    }
+    let rounded = if value < 10.0 {
+        (value * 10.0).ceil() / 10.0
+    } else {
+        value.ceil()
+    };
+    if rounded >= base as f64 && unit < suffixes.len() - 1 {
+        format!("1.0{}", suffixes[unit + 1])
+    } else if rounded < 10.0 {
+        format!("{:.1}{}", rounded, suffixes[unit])
+    } else {
+        format!("{:.0}{}", rounded, suffixes[unit])
+    }
+}
+
+// `blocks` is in the 512-byte units of st_blocks; plain output uses 1K blocks
+fn format_blocks(blocks: u64, style: SizeStyle) -> String {
+    match style {
+        SizeStyle::Bytes => (blocks * 512).div_ceil(1024).to_string(),
+        _ => format_size(blocks * 512, style),
+    }
+}
+
+fn format_output(paths: &[PathBuf], config: &Config) -> MyResult<String> {
+    let mut rows = Vec::new();
     for path in paths {
-        let metadata = path.metadata()?;
-        let username = users::get_user_by_uid(metadata.uid())
+        let metadata = fs::symlink_metadata(path)?;
+        let name = format_name(path, &path.display().to_string(), &metadata, config, false);
+        rows.push((path.clone(), name));
+    }
+    format_table(&rows, config)
+}
+
+// Each row is an entry and its rendered name cell. The name is the last
+// column, which tabular never pads, so color escapes and tree connectors in
+// it can't throw off the alignment.
+fn format_table(rows: &[(PathBuf, String)], config: &Config) -> MyResult<String> {
+    let mut fmt = String::new();
+    if config.inode {
+        fmt.push_str("{:>}  ");
+    }
+    if config.blocks {
+        fmt.push_str("{:>}  ");
+    }
+    fmt.push_str("{:<}{:<}  {:>}  {:<}  {:<}  {:>}  {:<}  {:<}");
+    let mut table = Table::new(&fmt);
+    let now = Local::now();
+    for (path, name) in rows {
+        let metadata = fs::symlink_metadata(path)?;
+        // Fall back to the numeric ids when there is no passwd/group entry
+        let (user, group) = if config.numeric_ids {
+            (None, None)
+        } else {
+            (
+                users::get_user_by_uid(metadata.uid()),
+                users::get_group_by_gid(metadata.gid()),
+            )
+        };
+        let username = user
             .map(|user| user.name().to_string_lossy().into_owned())
-            .unwrap();
-        let grpname = users::get_group_by_gid(metadata.gid())
+            .unwrap_or_else(|| metadata.uid().to_string());
+        let grpname = group
             .map(|grp| grp.name().to_string_lossy().into_owned())
-            .unwrap();
-        let modified: DateTime<Utc> = From::from(metadata.modified()?);
-        let mode = format_mode(metadata.mode());
+            .unwrap_or_else(|| metadata.gid().to_string());
+        let modified: DateTime<Local> = From::from(metadata.modified()?);
+        let mode = format_mode(metadata.mode()) + xattr_indicator(path);
 
+        let mut row = Row::new();
+        if config.inode {
+            row = row.with_cell(metadata.ino());
+        }
+        if config.blocks {
+            row = row.with_cell(format_blocks(metadata.blocks(), config.size_style));
+        }
         table.add_row(
-            Row::new()
-                .with_cell(if metadata.is_dir() { "d" } else { "-" })
+            row.with_cell(format_file_type(&metadata.file_type()))
                 .with_cell(mode)
                 .with_cell(metadata.nlink())
                 .with_cell(username)
                 .with_cell(grpname)
-                .with_cell(metadata.size())
-                .with_cell(modified.format("%b %d %g"))
-                .with_cell(path.display()),
+                .with_cell(format_size(metadata.size(), config.size_style))
+                .with_cell(format_time(&modified, &now, &config.time_style))
+                .with_cell(name),
         );
     }
-    Ok(format!("{}", table))
+    let table = format!("{}", table);
+    if !config.xattr {
+        return Ok(table);
+    }
+    let mut out = String::new();
+    for (line, (path, _)) in table.lines().zip(rows) {
+        out.push_str(line);
+        out.push('\n');
+        for attr in format_xattrs(path) {
+            out.push_str(&attr);
+            out.push('\n');
+        }
+    }
+    Ok(out)
 }
 
-fn format_chunk((r, w, x): (u32, u32, u32)) -> String {
+// The setuid, setgid and sticky bits replace the execute slot of their
+// chunk, in lowercase when execute is also set and uppercase otherwise.
+fn format_chunk((r, w, x): (u32, u32, u32), special: u32, special_char: &str) -> String {
     let r = if r != 0 { "r" } else { "-" };
     let w = if w != 0 { "w" } else { "-" };
-    let x = if x != 0 { "x" } else { "-" };
+    let x = match (x != 0, special != 0) {
+        (true, true) => special_char.to_string(),
+        (false, true) => special_char.to_uppercase(),
+        (true, false) => "x".to_string(),
+        (false, false) => "-".to_string(),
+    };
     format!("{}{}{}", r, w, x)
 }
 
@@ -132,16 +1219,50 @@ fn format_mode(mode: u32) -> String {
     let others = (mode & 0o004, mode & 0o002, mode & 0o001);
     format!(
         "{}{}{}",
-        format_chunk(user),
-        format_chunk(grp),
-        format_chunk(others)
+        format_chunk(user, mode & 0o4000, "s"),
+        format_chunk(grp, mode & 0o2000, "s"),
+        format_chunk(others, mode & 0o1000, "t")
     )
 }
 
 #[cfg(test)]
 mod test {
-    use super::{find_files, format_mode, format_output};
-    use std::path::PathBuf;
+    use super::{
+        display_width, find_files, format_blocks, format_file_type, format_grid, format_mode,
+        format_name, format_output, format_size, format_time, format_xattrs, list_xattrs,
+        parse_time_style, sort_paths, version_cmp, walk_tree, xattr_indicator, Config, Layout,
+        LsColors, SizeStyle, SortKey, TimeStyle,
+    };
+    use chrono::{Local, TimeZone};
+    use std::{
+        cmp::Ordering, ffi::CString, fs, os::unix::ffi::OsStrExt, os::unix::fs::symlink,
+        path::PathBuf,
+    };
+
+    fn long_config() -> Config {
+        Config {
+            paths: vec![],
+            long: true,
+            show_hidden: false,
+            sort: SortKey::Name,
+            reverse: false,
+            group_dirs_first: false,
+            layout: Layout::OneLine,
+            width: 80,
+            time_style: TimeStyle::Locale,
+            size_style: SizeStyle::Bytes,
+            blocks: false,
+            numeric_ids: false,
+            colors: None,
+            classify: false,
+            tree: false,
+            max_depth: None,
+            ascii: false,
+            inode: false,
+            xattr: false,
+        }
+    }
+
     fn long_match(
         line: &str,
         expected_name: &str,
@@ -248,17 +1369,381 @@ mod test {
             ]
         );
     }
+    #[test]
+    fn test_sort_paths() {
+        let paths = find_files(&["tests/inputs".to_string()], false).unwrap();
+        let sorted: Vec<_> = sort_paths(paths.clone(), SortKey::Name, false, false)
+            .iter()
+            .map(|entry| entry.display().to_string())
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                "tests/inputs/bustle.txt",
+                "tests/inputs/dir",
+                "tests/inputs/empty.txt",
+                "tests/inputs/fox.txt",
+            ]
+        );
+        // Reversing flips the order
+        let sorted: Vec<_> = sort_paths(paths.clone(), SortKey::Name, true, false)
+            .iter()
+            .map(|entry| entry.display().to_string())
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                "tests/inputs/fox.txt",
+                "tests/inputs/empty.txt",
+                "tests/inputs/dir",
+                "tests/inputs/bustle.txt",
+            ]
+        );
+        // Directories come first but are otherwise still sorted
+        let sorted: Vec<_> = sort_paths(paths, SortKey::Name, true, true)
+            .iter()
+            .map(|entry| entry.display().to_string())
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                "tests/inputs/dir",
+                "tests/inputs/fox.txt",
+                "tests/inputs/empty.txt",
+                "tests/inputs/bustle.txt",
+            ]
+        );
+        // Largest files first
+        let files = vec![
+            PathBuf::from("tests/inputs/empty.txt"),
+            PathBuf::from("tests/inputs/fox.txt"),
+            PathBuf::from("tests/inputs/bustle.txt"),
+        ];
+        let sorted: Vec<_> = sort_paths(files, SortKey::Size, false, false)
+            .iter()
+            .map(|entry| entry.display().to_string())
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                "tests/inputs/bustle.txt",
+                "tests/inputs/fox.txt",
+                "tests/inputs/empty.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_cmp() {
+        assert_eq!(version_cmp(b"file2", b"file10"), Ordering::Less);
+        assert_eq!(version_cmp(b"file10", b"file2"), Ordering::Greater);
+        assert_eq!(version_cmp(b"v1.9.0", b"v1.10.0"), Ordering::Less);
+        assert_eq!(version_cmp(b"a", b"a1"), Ordering::Less);
+        assert_eq!(version_cmp(b"abc", b"abd"), Ordering::Less);
+        assert_eq!(version_cmp(b"file", b"file"), Ordering::Equal);
+        // Leading zeros only break ties
+        assert_eq!(version_cmp(b"file01", b"file1"), Ordering::Less);
+        assert_eq!(version_cmp(b"file01", b"file2"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_format_grid() {
+        let names: Vec<String> = ["a", "bb", "ccc", "dddd", "e"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        // Everything fits on one line
+        assert_eq!(format_grid(&names, 80, false), "a  bb  ccc  dddd  e\n");
+        // Columns are filled top to bottom
+        assert_eq!(format_grid(&names, 12, false), "a   ccc   e\nbb  dddd\n");
+        // Rows are filled left to right
+        assert_eq!(format_grid(&names, 12, true), "a    bb\nccc  dddd\ne\n");
+        // A name wider than the terminal gets a line of its own
+        assert_eq!(format_grid(&names, 2, false), "a\nbb\nccc\ndddd\ne\n");
+        // Wide characters take two columns each
+        let names: Vec<String> = ["日本", "ab", "cd"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(format_grid(&names, 9, false), "日本  cd\nab\n");
+        assert_eq!(format_grid(&[], 80, false), "");
+    }
+
     #[test]
     fn test_format_mode() {
         assert_eq!(format_mode(0o755), "rwxr-xr-x");
         assert_eq!(format_mode(0o421), "r---w---x");
+        // Special bits with and without the matching execute bit
+        assert_eq!(format_mode(0o4755), "rwsr-xr-x");
+        assert_eq!(format_mode(0o4644), "rwSr--r--");
+        assert_eq!(format_mode(0o2775), "rwxrwsr-x");
+        assert_eq!(format_mode(0o2664), "rw-rwSr--");
+        assert_eq!(format_mode(0o1777), "rwxrwxrwt");
+        assert_eq!(format_mode(0o1666), "rw-rw-rwT");
+    }
+
+    #[test]
+    fn test_format_file_type() {
+        let file_type = |path: &str| fs::symlink_metadata(path).unwrap().file_type();
+        assert_eq!(format_file_type(&file_type("tests/inputs/fox.txt")), "-");
+        assert_eq!(format_file_type(&file_type("tests/inputs/dir")), "d");
+        assert_eq!(format_file_type(&file_type("/dev/null")), "c");
+    }
+
+    #[test]
+    fn test_parse_time_style() {
+        assert_eq!(parse_time_style("locale").unwrap(), TimeStyle::Locale);
+        assert_eq!(parse_time_style("iso").unwrap(), TimeStyle::Iso);
+        assert_eq!(parse_time_style("long-iso").unwrap(), TimeStyle::LongIso);
+        assert_eq!(parse_time_style("full-iso").unwrap(), TimeStyle::FullIso);
+        assert_eq!(
+            parse_time_style("+%Y").unwrap(),
+            TimeStyle::Format {
+                old: "%Y".to_string(),
+                recent: "%Y".to_string()
+            }
+        );
+        assert_eq!(
+            parse_time_style("+%Y\n%H:%M").unwrap(),
+            TimeStyle::Format {
+                old: "%Y".to_string(),
+                recent: "%H:%M".to_string()
+            }
+        );
+        let res = parse_time_style("foo");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "foo");
+        // An invalid strftime specifier is rejected up front
+        assert!(parse_time_style("+%Q").is_err());
+    }
+
+    #[test]
+    fn test_format_time() {
+        let now = Local.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let recent = Local.with_ymd_and_hms(2026, 9, 5, 8, 30, 0).unwrap();
+        let old = Local.with_ymd_and_hms(2025, 1, 15, 8, 30, 0).unwrap();
+        let future = Local.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            format_time(&recent, &now, &TimeStyle::Locale),
+            "Sep  5 08:30"
+        );
+        assert_eq!(format_time(&old, &now, &TimeStyle::Locale), "Jan 15  2025");
+        assert_eq!(
+            format_time(&future, &now, &TimeStyle::Locale),
+            "Jan  1  2027"
+        );
+        assert_eq!(format_time(&recent, &now, &TimeStyle::Iso), "09-05 08:30");
+        assert_eq!(format_time(&old, &now, &TimeStyle::Iso), "2025-01-15");
+        assert_eq!(
+            format_time(&old, &now, &TimeStyle::LongIso),
+            "2025-01-15 08:30"
+        );
+        assert!(format_time(&old, &now, &TimeStyle::FullIso)
+            .starts_with("2025-01-15 08:30:00.000000000 "));
+        let style = parse_time_style("+%Y\n%H:%M").unwrap();
+        assert_eq!(format_time(&recent, &now, &style), "08:30");
+        assert_eq!(format_time(&old, &now, &style), "2025");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(193, SizeStyle::Bytes), "193");
+        assert_eq!(format_size(4096, SizeStyle::Bytes), "4096");
+        assert_eq!(format_size(193, SizeStyle::Human), "193");
+        assert_eq!(format_size(1023, SizeStyle::Human), "1023");
+        assert_eq!(format_size(1024, SizeStyle::Human), "1.0K");
+        assert_eq!(format_size(1025, SizeStyle::Human), "1.1K");
+        assert_eq!(format_size(4096, SizeStyle::Human), "4.0K");
+        assert_eq!(format_size(10 * 1024, SizeStyle::Human), "10K");
+        assert_eq!(format_size(10 * 1024 + 1, SizeStyle::Human), "11K");
+        assert_eq!(format_size(1024 * 1024 - 1, SizeStyle::Human), "1.0M");
+        assert_eq!(
+            format_size(3 * 1024 * 1024 * 1024, SizeStyle::Human),
+            "3.0G"
+        );
+        assert_eq!(format_size(999, SizeStyle::Si), "999");
+        assert_eq!(format_size(1000, SizeStyle::Si), "1.0k");
+        assert_eq!(format_size(4096, SizeStyle::Si), "4.1k");
+        assert_eq!(format_size(25_000_000, SizeStyle::Si), "25M");
+    }
+
+    #[test]
+    fn test_format_blocks() {
+        assert_eq!(format_blocks(0, SizeStyle::Bytes), "0");
+        assert_eq!(format_blocks(8, SizeStyle::Bytes), "4");
+        assert_eq!(format_blocks(1, SizeStyle::Bytes), "1");
+        assert_eq!(format_blocks(8, SizeStyle::Human), "4.0K");
+    }
+
+    #[test]
+    fn test_ls_colors() {
+        let colors = LsColors::parse("di=01;34:ln=01;36:ex=01;32:*.txt=33:*.TXT=35:bogus");
+        let style = |path: &str| {
+            let metadata = fs::symlink_metadata(path).unwrap();
+            colors.style(std::path::Path::new(path), &metadata)
+        };
+        assert_eq!(style("tests/inputs/dir"), Some("01;34"));
+        assert_eq!(style("tests/inputs/fox.txt"), Some("33"));
+        assert_eq!(style("/dev/null"), None);
+        // Later suffix rules win over earlier ones
+        let colors = LsColors::parse("*.txt=33:*fox.txt=31");
+        let metadata = fs::metadata("tests/inputs/fox.txt").unwrap();
+        assert_eq!(
+            colors.style(std::path::Path::new("tests/inputs/fox.txt"), &metadata),
+            Some("31")
+        );
+    }
+
+    #[test]
+    fn test_format_name() {
+        let mut config = long_config();
+        let path = PathBuf::from("tests/inputs/dir");
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(
+            format_name(&path, "tests/inputs/dir", &metadata, &config, true),
+            "tests/inputs/dir"
+        );
+        config.classify = true;
+        assert_eq!(
+            format_name(&path, "tests/inputs/dir", &metadata, &config, true),
+            "tests/inputs/dir/"
+        );
+        config.colors = Some(LsColors::parse("di=01;34"));
+        // The indicator stays outside of the colored name
+        assert_eq!(
+            format_name(&path, "tests/inputs/dir", &metadata, &config, true),
+            "\x1b[01;34mtests/inputs/dir\x1b[0m/"
+        );
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("fox.txt"), 7);
+        assert_eq!(display_width("\x1b[01;34mdir\x1b[0m/"), 4);
+        assert_eq!(display_width("\x1b[33m日本\x1b[0m"), 4);
+        // Colored names line up like plain ones
+        let names = vec![
+            "\x1b[01;34ma\x1b[0m".to_string(),
+            "bb".to_string(),
+            "ccc".to_string(),
+        ];
+        assert_eq!(
+            format_grid(&names, 8, false),
+            "\x1b[01;34ma\x1b[0m   ccc\nbb\n"
+        );
+    }
+
+    #[test]
+    fn test_walk_tree() {
+        let mut config = long_config();
+        config.ascii = true;
+        let mut rows = vec![];
+        let mut counts = (0, 0);
+        let res = walk_tree(
+            std::path::Path::new("tests/inputs"),
+            "",
+            1,
+            &config,
+            &mut rows,
+            &mut counts,
+        );
+        assert!(res.is_ok());
+        let names: Vec<_> = rows.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "|-- bustle.txt",
+                "|-- dir",
+                "|   `-- spiders.txt",
+                "|-- empty.txt",
+                "`-- fox.txt",
+            ]
+        );
+        assert_eq!(counts, (1, 4));
+        // Limiting the depth and showing hidden entries
+        config.max_depth = Some(1);
+        config.show_hidden = true;
+        config.group_dirs_first = true;
+        config.ascii = false;
+        let mut rows = vec![];
+        let mut counts = (0, 0);
+        let res = walk_tree(
+            std::path::Path::new("tests/inputs"),
+            "",
+            1,
+            &config,
+            &mut rows,
+            &mut counts,
+        );
+        assert!(res.is_ok());
+        let names: Vec<_> = rows.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "├── dir",
+                "├── .hidden",
+                "├── bustle.txt",
+                "├── empty.txt",
+                "└── fox.txt",
+            ]
+        );
+        assert_eq!(counts, (1, 4));
+    }
+
+    #[test]
+    fn test_xattrs() {
+        let path = std::env::temp_dir().join(format!("lsr-xattr-{}", std::process::id()));
+        fs::write(&path, "").unwrap();
+        assert_eq!(xattr_indicator(&path), "");
+        assert!(format_xattrs(&path).is_empty());
+        let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let name = CString::new("user.comment").unwrap();
+        let set = unsafe {
+            libc::lsetxattr(
+                c_path.as_ptr(),
+                name.as_ptr(),
+                b"hello".as_ptr() as *const libc::c_void,
+                5,
+                0,
+            )
+        };
+        // Not every filesystem supports user attributes
+        if set == 0 {
+            assert_eq!(list_xattrs(&path), ["user.comment"]);
+            assert_eq!(xattr_indicator(&path), "@");
+            assert_eq!(format_xattrs(&path), ["\tuser.comment: \"hello\""]);
+            let mut config = long_config();
+            config.xattr = true;
+            let out = format_output(std::slice::from_ref(&path), &config).unwrap();
+            let lines: Vec<_> = out.lines().collect();
+            assert_eq!(lines.len(), 2);
+            assert_eq!(&lines[0][10..11], "@");
+            assert_eq!(lines[1], "\tuser.comment: \"hello\"");
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_format_output_symlink() {
+        let dir = std::env::temp_dir().join(format!("lsr-symlink-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let link = dir.join("link");
+        let _ = fs::remove_file(&link);
+        symlink("target.txt", &link).unwrap();
+        let res = format_output(std::slice::from_ref(&link), &long_config());
+        fs::remove_dir_all(&dir).unwrap();
+        let out = res.unwrap();
+        assert!(out.starts_with("lrwxrwxrwx"));
+        assert!(out
+            .trim_end()
+            .ends_with(&format!("{} -> target.txt", link.display())));
     }
 
     #[test]
     fn test_format_output_one() {
         let bustle_path = "tests/inputs/bustle.txt";
         let bustle = PathBuf::from(bustle_path);
-        let res = format_output(&[bustle]);
+        let res = format_output(&[bustle], &long_config());
         assert!(res.is_ok());
         let out = res.unwrap();
         let lines: Vec<&str> = out.split("\n").filter(|s| !s.is_empty()).collect();
@@ -269,10 +1754,13 @@ mod test {
 
     #[test]
     fn test_format_output_two() {
-        let res = format_output(&[
-            PathBuf::from("tests/inputs/dir"),
-            PathBuf::from("tests/inputs/empty.txt"),
-        ]);
+        let res = format_output(
+            &[
+                PathBuf::from("tests/inputs/dir"),
+                PathBuf::from("tests/inputs/empty.txt"),
+            ],
+            &long_config(),
+        );
         assert!(res.is_ok());
         let out = res.unwrap();
         let mut lines: Vec<&str> = out.split("\n").filter(|s| !s.is_empty()).collect();
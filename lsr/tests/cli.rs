@@ -11,15 +11,19 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const BUSTLE: &str = "tests/inputs/bustle.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 
+// --------------------------------------------------
+fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(char::from)
+        .collect()
+}
+
 // --------------------------------------------------
 fn gen_bad_file() -> String {
     loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
+        let filename = random_string();
         if fs::metadata(&filename).is_err() {
             return filename;
         }
@@ -173,11 +177,132 @@ fn dir2_all() -> TestResult {
 }
 
 // --------------------------------------------------
-fn dir_long(args: &[&str], expected: &[(&str, &str, &str)]) -> TestResult {
+fn dir_sorted(args: &[&str], expected: &[&str]) -> TestResult {
     let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
     let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
     let lines: Vec<&str> =
         stdout.split("\n").filter(|s| !s.is_empty()).collect();
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+#[test]
+fn dir1_sorted() -> TestResult {
+    dir_sorted(
+        &["tests/inputs"],
+        &[
+            "tests/inputs/bustle.txt",
+            "tests/inputs/dir",
+            "tests/inputs/empty.txt",
+            "tests/inputs/fox.txt",
+        ],
+    )
+}
+
+#[test]
+fn dir1_sorted_reverse() -> TestResult {
+    dir_sorted(
+        &["-r", "tests/inputs"],
+        &[
+            "tests/inputs/fox.txt",
+            "tests/inputs/empty.txt",
+            "tests/inputs/dir",
+            "tests/inputs/bustle.txt",
+        ],
+    )
+}
+
+#[test]
+fn dir1_group_directories_first() -> TestResult {
+    dir_sorted(
+        &["--group-directories-first", "-a", "tests/inputs"],
+        &[
+            "tests/inputs/dir",
+            "tests/inputs/.hidden",
+            "tests/inputs/bustle.txt",
+            "tests/inputs/empty.txt",
+            "tests/inputs/fox.txt",
+        ],
+    )
+}
+
+#[test]
+fn files_sorted_by_size() -> TestResult {
+    dir_sorted(
+        &["-S", EMPTY, FOX, BUSTLE],
+        &[BUSTLE, FOX, EMPTY],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dir1_columns() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-C", "-w", "50", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs/bustle.txt  tests/inputs/empty.txt\n\
+             tests/inputs/dir         tests/inputs/fox.txt\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn dir1_across() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-x", "-w", "50", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs/bustle.txt  tests/inputs/dir\n\
+             tests/inputs/empty.txt   tests/inputs/fox.txt\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn dies_bad_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-C", "-w", "foo", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal width -- foo"));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_time_style() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--time-style", "foo", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time style -- foo"));
+    Ok(())
+}
+
+#[test]
+fn long_iso_time_style() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-l", "--time-style=long-iso", BUSTLE])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let parts: Vec<_> = stdout.split_whitespace().collect();
+    assert_eq!(parts.len(), 8);
+    assert_eq!(parts[5].len(), "2026-10-18".len());
+    assert_eq!(parts[6].len(), "12:00".len());
+    Ok(())
+}
+
+// --------------------------------------------------
+fn dir_long(args: &[&str], expected: &[(&str, &str, &str)]) -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut lines: Vec<&str> =
+        stdout.split("\n").filter(|s| !s.is_empty()).collect();
+    // Directory listings start with a total of the allocated blocks
+    assert!(lines.remove(0).starts_with("total "));
     assert_eq!(lines.len(), expected.len());
 
     let mut check = vec![];
@@ -245,3 +370,203 @@ fn dir2_long_all() -> TestResult {
         ],
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn human_readable() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("lsr-human-{}", random_string()));
+    fs::create_dir_all(&dir)?;
+    let file = dir.join("two_k.bin");
+    fs::write(&file, vec![0u8; 2048])?;
+    let filename = file.display().to_string();
+    let res = Command::cargo_bin(PRG)?.args(["-lh", &filename]).assert();
+    let stdout = String::from_utf8(res.get_output().stdout.clone())?;
+    fs::remove_dir_all(&dir)?;
+    let parts: Vec<_> = stdout.split_whitespace().collect();
+    assert_eq!(parts.get(4).unwrap(), &"2.0K");
+    Ok(())
+}
+
+#[test]
+fn numeric_ids() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?.args(["-n", BUSTLE]).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let parts: Vec<_> = stdout.split_whitespace().collect();
+    assert!(parts[2].parse::<u32>().is_ok());
+    assert!(parts[3].parse::<u32>().is_ok());
+    assert_eq!(parts.last().unwrap(), &BUSTLE);
+    Ok(())
+}
+
+#[test]
+fn blocks() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-1s", "tests/inputs"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("total "));
+    assert!(lines[3].trim_start().starts_with("0 tests/inputs/empty.txt"));
+    Ok(())
+}
+
+#[test]
+fn multiple_operands() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-1", "tests/inputs/dir", BUSTLE, FOX])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs/bustle.txt\n\
+             tests/inputs/fox.txt\n\
+             \n\
+             tests/inputs/dir:\n\
+             tests/inputs/dir/spiders.txt\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn color_always() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("LS_COLORS", "di=01;34:*.txt=33")
+        .args(["-1", "--color=always", "tests/inputs/dir", BUSTLE])
+        .assert()
+        .success()
+        .stdout(
+            "\x1b[33mtests/inputs/bustle.txt\x1b[0m\n\
+             \n\
+             tests/inputs/dir:\n\
+             \x1b[33mtests/inputs/dir/spiders.txt\x1b[0m\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn color_never() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("LS_COLORS", "di=01;34:*.txt=33")
+        .args(["-1", "--color=never", BUSTLE])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", BUSTLE));
+    Ok(())
+}
+
+#[test]
+fn color_columns_aligned() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("LS_COLORS", "di=01;34")
+        .args(["-C", "-w", "50", "--color=always", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs/bustle.txt  tests/inputs/empty.txt\n\
+             \x1b[01;34mtests/inputs/dir\x1b[0m         \
+             tests/inputs/fox.txt\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn classify() -> TestResult {
+    dir_sorted(
+        &["-1F", "tests/inputs"],
+        &[
+            "tests/inputs/bustle.txt",
+            "tests/inputs/dir/",
+            "tests/inputs/empty.txt",
+            "tests/inputs/fox.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tree() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--tree", "--charset=utf-8", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs\n\
+             ├── bustle.txt\n\
+             ├── dir\n\
+             │   └── spiders.txt\n\
+             ├── empty.txt\n\
+             └── fox.txt\n\
+             \n\
+             1 directory, 4 files\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn tree_ascii_depth_dirs_first() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--tree", "--charset=ascii", "-L", "1", "--dirs-first"])
+        .arg("tests/inputs")
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs\n\
+             |-- dir\n\
+             |-- bustle.txt\n\
+             |-- empty.txt\n\
+             `-- fox.txt\n\
+             \n\
+             1 directory, 3 files\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn tree_long() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["--tree", "-l", "--charset=ascii", "tests/inputs/dir"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with('d'));
+    assert!(lines[0].ends_with("  tests/inputs/dir"));
+    assert!(lines[1].starts_with('-'));
+    assert!(lines[1].ends_with("  `-- spiders.txt"));
+    assert_eq!(lines[3], "0 directories, 1 file");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_level() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--tree", "-L", "0", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal level -- 0"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn inode() -> TestResult {
+    use std::os::unix::fs::MetadataExt;
+    let ino = fs::metadata(BUSTLE)?.ino();
+    Command::cargo_bin(PRG)?
+        .args(["-i", BUSTLE])
+        .assert()
+        .success()
+        .stdout(format!("{} {}\n", ino, BUSTLE));
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["-li", BUSTLE])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let parts: Vec<_> = stdout.split_whitespace().collect();
+    assert_eq!(parts[0], ino.to_string());
+    assert_eq!(parts[1], "-rw-r--r--");
+    Ok(())
+}
@@ -1,14 +1,75 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use clap::{App, Arg};
+use regex::bytes::Regex;
+use std::collections::VecDeque;
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub enum TakeValue {
     PlusZero,
     TakeNum(i64),
+    Since(NaiveDateTime),
+    FromMatch(Regex),
+}
+
+impl PartialEq for TakeValue {
+    fn eq(&self, other: &TakeValue) -> bool {
+        match (self, other) {
+            (TakeValue::PlusZero, TakeValue::PlusZero) => true,
+            (TakeValue::TakeNum(a), TakeValue::TakeNum(b)) => a == b,
+            (TakeValue::Since(a), TakeValue::Since(b)) => a == b,
+            (TakeValue::FromMatch(a), TakeValue::FromMatch(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+// How to find the timestamp of a line for `--since`. A regex takes the
+// first capture group (or the whole match) as the timestamp, while a
+// strftime format must match the start of the line.
+#[derive(Debug)]
+pub enum TimeFormat {
+    Regex(Regex),
+    Strftime(String),
+}
+
+const DEFAULT_TIME_FORMAT: &str = r"(\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?)";
+
+impl TimeFormat {
+    fn timestamp(&self, line: &[u8]) -> Option<NaiveDateTime> {
+        match self {
+            TimeFormat::Regex(re) => {
+                let caps = re.captures(line)?;
+                let text = caps.get(1).or_else(|| caps.get(0))?;
+                parse_time(std::str::from_utf8(text.as_bytes()).ok()?).ok()
+            }
+            TimeFormat::Strftime(fmt) => {
+                let line = String::from_utf8_lossy(line);
+                NaiveDateTime::parse_and_remainder(&line, fmt)
+                    .map(|(time, _)| time)
+                    .or_else(|_| {
+                        NaiveDate::parse_and_remainder(&line, fmt)
+                            .map(|(date, _)| date.and_time(NaiveTime::MIN))
+                    })
+                    .ok()
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Follow {
+    Descriptor,
+    Name,
 }
 
 #[derive(Debug)]
@@ -17,6 +78,12 @@ pub struct Config {
     lines: TakeValue,
     bytes: Option<TakeValue>,
     quiet: bool,
+    chars: bool,
+    separator: u8,
+    time_format: TimeFormat,
+    follow: Option<Follow>,
+    sleep_interval: Duration,
+    pid: Option<i32>,
 }
 
 pub fn get_args() -> MyResult<Config> {
@@ -28,8 +95,8 @@ pub fn get_args() -> MyResult<Config> {
             Arg::with_name("files")
                 .value_name("FILE")
                 .help("Input file(s)")
-                .required(true)
-                .multiple(true),
+                .multiple(true)
+                .default_value("-"),
         )
         .arg(
             Arg::with_name("lines")
@@ -47,6 +114,48 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Number of bytes")
                 .conflicts_with("lines"),
         )
+        .arg(
+            Arg::with_name("chars")
+                .long("chars")
+                .help("With -c, count characters instead of bytes")
+                .requires("bytes")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("zero_terminated")
+                .short("z")
+                .long("zero-terminated")
+                .help("Line delimiter is NUL, not newline")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("separator")
+                .long("separator")
+                .value_name("BYTE")
+                .help("Line delimiter, such as \\x1e")
+                .conflicts_with("zero_terminated"),
+        )
+        .arg(
+            Arg::with_name("since")
+                .long("since")
+                .value_name("TIME")
+                .help("Start at the first line logged at or after TIME")
+                .conflicts_with_all(&["bytes", "from_match"]),
+        )
+        .arg(
+            Arg::with_name("time_format")
+                .long("time-format")
+                .value_name("FORMAT")
+                .help("Regex or strftime format of line timestamps")
+                .requires("since"),
+        )
+        .arg(
+            Arg::with_name("from_match")
+                .long("from-match")
+                .value_name("REGEX")
+                .help("Start at the last line matching REGEX")
+                .conflicts_with("bytes"),
+        )
         .arg(
             Arg::with_name("quiet")
                 .short("q")
@@ -54,14 +163,55 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Suppress headers")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("follow")
+                .short("f")
+                .long("follow")
+                .help("Output appended data as the file grows")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("follow_name")
+                .short("F")
+                .help("Follow by name, reopening the file if it is rotated or truncated")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("sleep_interval")
+                .short("s")
+                .long("sleep-interval")
+                .value_name("SECONDS")
+                .help("Seconds to sleep between checks when following")
+                .default_value("1.0"),
+        )
+        .arg(
+            Arg::with_name("pid")
+                .long("pid")
+                .value_name("PID")
+                .help("With -f, terminate after process PID dies"),
+        )
         .get_matches();
 
     let files = matches.values_of_lossy("files");
-    let lines = matches
+    let mut lines = matches
         .value_of("lines")
         .map(parse_num)
         .transpose()
         .map_err(|e| format!("illegal line count -- {}", e))?;
+    if let Some(since) = matches.value_of("since") {
+        let since = parse_time(since).map_err(|e| format!("invalid time -- {}", e))?;
+        lines = Some(TakeValue::Since(since));
+    }
+    if let Some(pattern) = matches.value_of("from_match") {
+        let re = Regex::new(pattern).map_err(|_| format!("invalid pattern -- {}", pattern))?;
+        lines = Some(TakeValue::FromMatch(re));
+    }
+    let time_format = parse_time_format(
+        matches
+            .value_of("time_format")
+            .unwrap_or(DEFAULT_TIME_FORMAT),
+    )
+    .map_err(|e| format!("invalid time format -- {}", e))?;
 
     let bytes = matches
         .value_of("bytes")
@@ -69,15 +219,104 @@ pub fn get_args() -> MyResult<Config> {
         .transpose()
         .map_err(|e| format!("illegal byte count -- {}", e))?;
     let quiet = matches.is_present("quiet");
+    let separator = if matches.is_present("zero_terminated") {
+        0
+    } else {
+        matches
+            .value_of("separator")
+            .map(parse_separator)
+            .transpose()
+            .map_err(|e| format!("invalid separator -- {}", e))?
+            .unwrap_or(b'\n')
+    };
+    let follow = if matches.is_present("follow_name") {
+        Some(Follow::Name)
+    } else if matches.is_present("follow") {
+        Some(Follow::Descriptor)
+    } else {
+        None
+    };
+    let sleep_interval = matches
+        .value_of("sleep_interval")
+        .map(parse_seconds)
+        .transpose()
+        .map_err(|e| format!("invalid number of seconds -- {}", e))?;
+    let pid = matches
+        .value_of("pid")
+        .map(|val| {
+            val.parse::<i32>()
+                .map_err(|_| format!("invalid PID -- {}", val))
+        })
+        .transpose()?;
 
     Ok(Config {
         files: files.unwrap(),
         lines: lines.unwrap(),
-        bytes: bytes,
+        bytes,
         quiet,
+        chars: matches.is_present("chars"),
+        separator,
+        time_format,
+        follow,
+        sleep_interval: sleep_interval.unwrap(),
+        pid,
     })
 }
 
+// Accepts a single byte, written literally or as `\n`, `\t`, `\0` or `\xHH`
+pub fn parse_separator(val: &str) -> MyResult<u8> {
+    match val {
+        "\\n" => Ok(b'\n'),
+        "\\t" => Ok(b'\t'),
+        "\\0" => Ok(0),
+        _ => match val.strip_prefix("\\x") {
+            Some(hex) if hex.len() == 2 => u8::from_str_radix(hex, 16).map_err(|_| From::from(val)),
+            Some(_) => Err(From::from(val)),
+            None if val.len() == 1 => Ok(val.as_bytes()[0]),
+            None => Err(From::from(val)),
+        },
+    }
+}
+
+pub fn parse_time(val: &str) -> MyResult<NaiveDateTime> {
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+    FORMATS
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(val, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(val, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .ok_or_else(|| From::from(val))
+}
+
+// Anything with a `%` is a strftime format, otherwise it is a regex
+pub fn parse_time_format(val: &str) -> MyResult<TimeFormat> {
+    if val.contains('%') {
+        if StrftimeItems::new(val).any(|item| item == Item::Error) {
+            return Err(From::from(val));
+        }
+        Ok(TimeFormat::Strftime(val.to_string()))
+    } else {
+        Regex::new(val)
+            .map(TimeFormat::Regex)
+            .map_err(|_| From::from(val))
+    }
+}
+
+pub fn parse_seconds(val: &str) -> MyResult<Duration> {
+    match val.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(From::from(val)),
+    }
+}
+
 pub fn parse_num(val: &str) -> MyResult<TakeValue> {
     match val {
         "+0" => Ok(TakeValue::PlusZero),
@@ -104,91 +343,632 @@ pub fn parse_num(val: &str) -> MyResult<TakeValue> {
 
 pub fn run(config: Config) -> MyResult<()> {
     let num_files = config.files.len();
+    let mut followed = Vec::new();
     for (file_num, filename) in config.files.iter().enumerate() {
-        match File::open(&filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(handle) => {
-                if !config.quiet && num_files > 1 {
-                    println!(
-                        "{}==> {} <==",
-                        if file_num > 0 { "\n" } else { "" },
-                        filename
-                    );
+        let print_header = || {
+            if !config.quiet && num_files > 1 {
+                let name = match filename.as_str() {
+                    "-" => "standard input",
+                    name => name,
+                };
+                println!("{}==> {} <==", if file_num > 0 { "\n" } else { "" }, name);
+            }
+        };
+        if filename == "-" {
+            print_header();
+            print_stream(io::stdin().lock(), &config)?;
+            continue;
+        }
+        match File::open(filename) {
+            Err(err) => {
+                eprintln!("{}: {}", filename, err);
+                // Following by name keeps retrying until the file appears
+                if config.follow == Some(Follow::Name) {
+                    followed.push(Followed::new(filename, None, 0)?);
                 }
-                let (lc, bc) = count_lines_bytes(&filename)?;
-                if let Some(bytes) = &config.bytes {
-                    print_bytes(handle, bytes, bc)?;
-                } else {
-                    let handle = BufReader::new(handle);
-                    print_lines(handle, &config.lines, lc)?;
+            }
+            Ok(mut handle) => {
+                print_header();
+                let metadata = handle.metadata()?;
+                if !metadata.is_file() {
+                    print_stream(BufReader::new(handle), &config)?;
+                    continue;
+                }
+                // Following picks up from the end of what was printed
+                let end = match (&config.bytes, &config.lines) {
+                    (Some(TakeValue::TakeNum(num)), _) if config.chars && *num <= 0 => {
+                        let start = find_char_start(&mut handle, num.unsigned_abs())?;
+                        print_from(&mut handle, start)?
+                    }
+                    (Some(_), _) if config.chars => {
+                        print_stream(BufReader::new(&handle), &config)?;
+                        handle.stream_position()?
+                    }
+                    (Some(bytes), _) => print_bytes(&mut handle, bytes, metadata.len() as i64)?,
+                    (None, TakeValue::Since(since)) => {
+                        let start = find_since_start(
+                            &mut handle,
+                            since,
+                            &config.time_format,
+                            config.separator,
+                        )?;
+                        print_from(&mut handle, start)?
+                    }
+                    (None, TakeValue::FromMatch(re)) => {
+                        let end = content_end(&mut handle, config.separator)?;
+                        let start = rfind_line(&mut handle, end, config.separator, |line| {
+                            re.is_match(line)
+                        })?;
+                        print_from(&mut handle, start.unwrap_or(metadata.len()))?
+                    }
+                    (None, TakeValue::TakeNum(num)) if *num <= 0 => {
+                        let start =
+                            find_tail_start(&mut handle, num.unsigned_abs(), config.separator)?;
+                        print_from(&mut handle, start)?
+                    }
+                    (None, _) => {
+                        print_stream(BufReader::new(&handle), &config)?;
+                        handle.stream_position()?
+                    }
+                };
+                if config.follow.is_some() {
+                    followed.push(Followed::new(filename, Some(handle), end)?);
                 }
             }
         }
     }
+    if !followed.is_empty() {
+        let last = followed.len() - 1;
+        follow(&config, followed, Some(last))?;
+    }
     Ok(())
 }
 
-pub fn count_lines_bytes(filename: &str) -> MyResult<(i64, i64)> {
-    let mut file = BufReader::new(File::open(filename)?);
-    let mut line_num = 0;
-    let mut byte_num = 0;
-    let mut buffer = Vec::new();
+// A file being followed. With `-F` the file can be missing or replaced,
+// so the handle is optional and `id` records which file it refers to.
+struct Followed {
+    name: String,
+    file: Option<File>,
+    pos: u64,
+    id: Option<(u64, u64)>,
+    missing: bool,
+}
+
+impl Followed {
+    // `pos` is where reading picks up
+    fn new(name: &str, file: Option<File>, pos: u64) -> MyResult<Followed> {
+        let id = match &file {
+            Some(handle) => {
+                let metadata = handle.metadata()?;
+                Some((metadata.dev(), metadata.ino()))
+            }
+            None => None,
+        };
+        Ok(Followed {
+            name: name.to_string(),
+            file,
+            pos,
+            id,
+            missing: false,
+        })
+    }
+
+    // Looks for a different file having taken our name. The old handle is
+    // kept until then, so whatever is still written to it is not lost.
+    fn replacement(&mut self) -> Option<(File, (u64, u64))> {
+        let metadata = match fs::metadata(&self.name) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                if self.file.is_some() && !self.missing {
+                    eprintln!("{}: file has become inaccessible", self.name);
+                    self.missing = true;
+                }
+                return None;
+            }
+        };
+        self.missing = false;
+        let id = (metadata.dev(), metadata.ino());
+        if self.id == Some(id) {
+            return None;
+        }
+        File::open(&self.name).ok().map(|file| (file, id))
+    }
+
+    fn switch_to(&mut self, file: File, id: (u64, u64)) {
+        eprintln!(
+            "{}: file has {}; following new file",
+            self.name,
+            if self.id.is_some() {
+                "been replaced"
+            } else {
+                "appeared"
+            }
+        );
+        self.file = Some(file);
+        self.pos = 0;
+        self.id = Some(id);
+    }
+
+    // The number of bytes appended since the last read. A file that
+    // shrank was truncated, so reading starts over from the beginning.
+    fn appended(&mut self) -> io::Result<u64> {
+        let len = match &self.file {
+            Some(file) => file.metadata()?.len(),
+            None => return Ok(0),
+        };
+        if len < self.pos {
+            eprintln!("{}: file truncated", self.name);
+            self.pos = 0;
+        }
+        Ok(len - self.pos)
+    }
+
+    fn copy_appended(&mut self, num_bytes: u64, out: &mut impl Write) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.seek(SeekFrom::Start(self.pos))?;
+            self.pos += io::copy(&mut file.take(num_bytes), out)?;
+        }
+        Ok(())
+    }
+}
+
+fn follow(config: &Config, mut files: Vec<Followed>, mut last: Option<usize>) -> MyResult<()> {
+    let watcher = Watcher::new();
+    for file in &files {
+        watcher.watch(&file.name);
+    }
+    let show_headers = !config.quiet && files.len() > 1;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
     loop {
-        let bytes_read = file.read_until(b'\n', &mut buffer)?;
-        if bytes_read == 0 {
-            break;
+        // Check before reading so that the last writes of the process
+        // are still printed
+        let alive = config.pid.is_none_or(process_alive);
+        for (i, file) in files.iter_mut().enumerate() {
+            if config.follow == Some(Follow::Name) {
+                if let Some((new_file, id)) = file.replacement() {
+                    // Finish the old file before switching to its replacement
+                    print_appended(file, i, &mut last, show_headers, &mut out)?;
+                    file.switch_to(new_file, id);
+                    watcher.watch(&file.name);
+                }
+            }
+            print_appended(file, i, &mut last, show_headers, &mut out)?;
+        }
+        out.flush()?;
+        if !alive {
+            return Ok(());
         }
-        line_num += 1;
-        byte_num += bytes_read as i64;
-        buffer.clear();
+        watcher.wait(config.sleep_interval);
     }
-    Ok((line_num, byte_num))
 }
 
-pub fn print_lines(
-    mut file: impl BufRead,
-    num_lines: &TakeValue,
-    total_lines: i64,
+// Prints what was appended to the `i`th file, with a header when the
+// last output came from another file
+fn print_appended(
+    file: &mut Followed,
+    i: usize,
+    last: &mut Option<usize>,
+    show_headers: bool,
+    out: &mut impl Write,
 ) -> MyResult<()> {
-    let idx = get_start_index(num_lines, total_lines);
-    match idx {
-        Some(idx) => {
-            let mut line_num = 0;
-            let mut buf = Vec::new();
+    let num_bytes = file.appended()?;
+    if num_bytes == 0 {
+        return Ok(());
+    }
+    if show_headers && *last != Some(i) {
+        writeln!(out, "\n==> {} <==", file.name)?;
+    }
+    *last = Some(i);
+    file.copy_appended(num_bytes, out)?;
+    Ok(())
+}
+
+fn process_alive(pid: i32) -> bool {
+    let alive = unsafe { libc::kill(pid, 0) == 0 };
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+// Waits for changes to the followed files, using inotify where it is
+// available and falling back to sleeping for the whole interval.
+struct Watcher {
+    fd: Option<i32>,
+}
+
+impl Watcher {
+    #[cfg(target_os = "linux")]
+    fn new() -> Watcher {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        Watcher {
+            fd: if fd >= 0 { Some(fd) } else { None },
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new() -> Watcher {
+        Watcher { fd: None }
+    }
+
+    // Watching the parent directory too catches a file being created or
+    // renamed into place after rotation
+    #[cfg(target_os = "linux")]
+    fn watch(&self, name: &str) {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let fd = match self.fd {
+            Some(fd) => fd,
+            None => return,
+        };
+        let path = Path::new(name);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let watches = [
+            (
+                path,
+                libc::IN_MODIFY | libc::IN_ATTRIB | libc::IN_DELETE_SELF | libc::IN_MOVE_SELF,
+            ),
+            (
+                dir,
+                libc::IN_CREATE | libc::IN_MOVED_TO | libc::IN_MOVED_FROM | libc::IN_DELETE,
+            ),
+        ];
+        for (path, mask) in watches {
+            if let Ok(path) = CString::new(path.as_os_str().as_bytes()) {
+                unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) };
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn watch(&self, _name: &str) {}
+
+    fn wait(&self, timeout: Duration) {
+        let fd = match self.fd {
+            Some(fd) => fd,
+            None => return thread::sleep(timeout),
+        };
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let millis = timeout.as_millis().min(i32::MAX as u128) as i32;
+        unsafe { libc::poll(&mut pollfd, 1, millis) };
+        // Only the wakeup matters, so discard the queued events
+        let mut buf = [0u8; 4096];
+        while unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) } > 0 {}
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        if let Some(fd) = self.fd {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+// UTF-8 continuation bytes look like 0b10xxxxxx; every other byte starts
+// a character
+fn is_char_start(byte: u8) -> bool {
+    byte & 0xc0 != 0x80
+}
+
+// Tails input that can only be read once. Counts from the start are
+// streamed, and counts from the end keep a ring buffer of the last lines
+// or bytes.
+fn print_stream(mut file: impl BufRead, config: &Config) -> MyResult<()> {
+    let mut out = io::stdout();
+    let take_val = config.bytes.as_ref().unwrap_or(&config.lines);
+    let num = match take_val {
+        TakeValue::PlusZero => 1,
+        TakeValue::TakeNum(num) => *num,
+        TakeValue::Since(since) => return print_stream_since(file, since, config),
+        TakeValue::FromMatch(re) => return print_stream_from_match(file, re, config),
+    };
+    if num == 0 {
+        return Ok(());
+    }
+    if num < 0 {
+        let num = num.unsigned_abs() as usize;
+        if config.bytes.is_some() {
+            // A character is at most four bytes
+            let limit = if config.chars {
+                num.saturating_mul(4)
+            } else {
+                num
+            };
+            let mut last = VecDeque::with_capacity(limit.min(BLOCK_SIZE as usize));
+            let mut buf = vec![0; BLOCK_SIZE as usize];
             loop {
-                let bytes_read = file.read_until(b'\n', &mut buf)?;
+                let bytes_read = file.read(&mut buf)?;
                 if bytes_read == 0 {
                     break;
                 }
-                if line_num >= idx {
-                    print!("{}", String::from_utf8_lossy(&buf));
-                }
-                line_num += 1;
+                last.extend(&buf[..bytes_read]);
+                let excess = last.len().saturating_sub(limit);
+                last.drain(..excess);
+            }
+            let mut last = Cursor::new(last.make_contiguous());
+            let start = if config.chars {
+                find_char_start(&mut last, num as u64)?
+            } else {
+                0
+            };
+            out.write_all(&last.into_inner()[start as usize..])?;
+        } else {
+            let mut last = VecDeque::new();
+            let mut line = Vec::new();
+            while file.read_until(config.separator, &mut line)? > 0 {
+                last.push_back(line);
+                // Reuse the oldest line's allocation once the ring is full
+                line = if last.len() > num {
+                    last.pop_front().unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
+                line.clear();
+            }
+            for line in last {
+                out.write_all(&line)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let skip = (num - 1) as u64;
+    match config.bytes {
+        Some(_) if config.chars => skip_chars(&mut file, skip)?,
+        Some(_) => {
+            io::copy(&mut (&mut file).take(skip), &mut io::sink())?;
+        }
+        None => {
+            let mut buf = Vec::new();
+            for _ in 0..skip {
                 buf.clear();
+                if file.read_until(config.separator, &mut buf)? == 0 {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    io::copy(&mut file, &mut out)?;
+    Ok(())
+}
+
+// Consumes the first `num_chars` characters
+fn skip_chars(file: &mut impl BufRead, num_chars: u64) -> io::Result<()> {
+    let mut seen = 0;
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let len = buf.len();
+        for (i, &byte) in buf.iter().enumerate() {
+            if is_char_start(byte) {
+                if seen == num_chars {
+                    file.consume(i);
+                    return Ok(());
+                }
+                seen += 1;
+            }
+        }
+        file.consume(len);
+    }
+}
+
+// Prints from `start` to the end, returning the offset it stopped at
+fn print_from<T: Read + Seek>(mut file: T, start: u64) -> MyResult<u64> {
+    file.seek(SeekFrom::Start(start))?;
+    let copied = io::copy(&mut file, &mut io::stdout())?;
+    Ok(start + copied)
+}
+
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+// The length of a file without its trailing separator
+fn content_end<T: Read + Seek>(file: &mut T, separator: u8) -> io::Result<u64> {
+    let end = file.seek(SeekFrom::End(0))?;
+    if end == 0 {
+        return Ok(end);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::Start(end - 1))?;
+    file.read_exact(&mut last)?;
+    Ok(if last[0] == separator { end - 1 } else { end })
+}
+
+// Finds the offset where the last `num_lines` lines start, where each line
+// ends with `separator`
+fn find_tail_start<T: Read + Seek>(file: &mut T, num_lines: u64, separator: u8) -> io::Result<u64> {
+    if num_lines == 0 {
+        return file.seek(SeekFrom::End(0));
+    }
+    // A trailing separator ends the last line rather than starting another
+    let end = content_end(file, separator)?;
+    Ok(rfind_nth(file, end, num_lines, |byte| byte == separator)?.map_or(0, |pos| pos + 1))
+}
+
+// Finds the offset where the last `num_chars` characters start
+fn find_char_start<T: Read + Seek>(file: &mut T, num_chars: u64) -> io::Result<u64> {
+    let end = file.seek(SeekFrom::End(0))?;
+    if num_chars == 0 {
+        return Ok(end);
+    }
+    Ok(rfind_nth(file, end, num_chars, is_char_start)?.unwrap_or(0))
+}
+
+// Finds the `num`th byte before `end` that matches `pred` by reading
+// backwards in blocks, so only the tail of the file is read
+fn rfind_nth<T: Read + Seek>(
+    file: &mut T,
+    mut end: u64,
+    num: u64,
+    pred: impl Fn(u8) -> bool,
+) -> io::Result<Option<u64>> {
+    let mut buf = vec![0; BLOCK_SIZE.min(end) as usize];
+    let mut found = 0;
+    while end > 0 {
+        let start = end.saturating_sub(BLOCK_SIZE);
+        let block = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(block)?;
+        for (i, _) in block.iter().enumerate().rev().filter(|(_, &b)| pred(b)) {
+            found += 1;
+            if found == num {
+                return Ok(Some(start + i as u64));
             }
-            Ok(())
         }
-        None => Ok(()),
+        end = start;
+    }
+    Ok(None)
+}
+
+// Finds the start of the last line before `end` for which `pred` holds,
+// reading backwards in blocks like `rfind_nth`
+fn rfind_line<T: Read + Seek>(
+    file: &mut T,
+    mut end: u64,
+    separator: u8,
+    pred: impl Fn(&[u8]) -> bool,
+) -> io::Result<Option<u64>> {
+    // The end of a line that started in an earlier block
+    let mut carry = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(BLOCK_SIZE);
+        let mut data = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut data)?;
+        data.extend_from_slice(&carry);
+        let mut line_end = data.len();
+        for i in (0..data.len()).rev().filter(|&i| data[i] == separator) {
+            if pred(&data[i + 1..line_end]) {
+                return Ok(Some(start + i as u64 + 1));
+            }
+            line_end = i;
+        }
+        data.truncate(line_end);
+        carry = data;
+        end = start;
+    }
+    Ok(if pred(&carry) { Some(0) } else { None })
+}
+
+// The first line with a timestamp at or after `pos`, and its offset
+fn first_timestamp<T: Read + Seek>(
+    file: &mut T,
+    mut pos: u64,
+    time_format: &TimeFormat,
+    separator: u8,
+) -> io::Result<(u64, Option<NaiveDateTime>)> {
+    let mut file = BufReader::new(file);
+    let mut line = Vec::new();
+    if pos > 0 {
+        // Skip to the start of the next line
+        file.seek(SeekFrom::Start(pos - 1))?;
+        pos += file.read_until(separator, &mut line)? as u64 - 1;
+    } else {
+        file.seek(SeekFrom::Start(0))?;
+    }
+    loop {
+        line.clear();
+        let bytes_read = file.read_until(separator, &mut line)?;
+        if bytes_read == 0 {
+            return Ok((pos, None));
+        }
+        if let Some(time) = time_format.timestamp(&line) {
+            return Ok((pos, Some(time)));
+        }
+        pos += bytes_read as u64;
     }
 }
 
-fn print_bytes<T: Read + Seek>(
-    mut file: T,
-    num_bytes: &TakeValue,
-    total_bytes: i64,
+// Binary searches for the first line logged at or after `since`. Lines
+// without a timestamp belong to the line before them.
+fn find_since_start<T: Read + Seek>(
+    file: &mut T,
+    since: &NaiveDateTime,
+    time_format: &TimeFormat,
+    separator: u8,
+) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match first_timestamp(file, mid, time_format, separator)?.1 {
+            Some(time) if time < *since => lo = mid + 1,
+            _ => hi = mid,
+        }
+    }
+    Ok(match first_timestamp(file, lo, time_format, separator)? {
+        (_, None) => len,
+        // Keep any lines before the first timestamp
+        (_, Some(_)) if lo == 0 => 0,
+        (pos, Some(_)) => pos,
+    })
+}
+
+fn print_stream_since(
+    mut file: impl BufRead,
+    since: &NaiveDateTime,
+    config: &Config,
 ) -> MyResult<()> {
-    let idx = get_start_index(num_bytes, total_bytes);
-    match idx {
-        Some(idx) => {
-            let mut buffer = Vec::new();
-            file.seek(std::io::SeekFrom::Start(idx))?;
-            file.read_to_end(&mut buffer)?;
-            if !buffer.is_empty() {
-                print!("{}", String::from_utf8_lossy(&buffer));
+    let mut out = io::stdout();
+    // Lines before the first timestamp are printed if that timestamp is
+    // already late enough
+    let mut pending = Some(Vec::new());
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if file.read_until(config.separator, &mut line)? == 0 {
+            return Ok(());
+        }
+        match config.time_format.timestamp(&line) {
+            Some(time) if time >= *since => break,
+            Some(_) => pending = None,
+            None => {
+                if let Some(pending) = &mut pending {
+                    pending.extend_from_slice(&line);
+                }
             }
-            Ok(())
         }
-        None => Ok(()),
+    }
+    if let Some(pending) = pending {
+        out.write_all(&pending)?;
+    }
+    out.write_all(&line)?;
+    io::copy(&mut file, &mut out)?;
+    Ok(())
+}
+
+fn print_stream_from_match(mut file: impl BufRead, re: &Regex, config: &Config) -> MyResult<()> {
+    // Everything from the last matching line on
+    let mut tail: Option<Vec<u8>> = None;
+    let mut line = Vec::new();
+    while file.read_until(config.separator, &mut line)? > 0 {
+        let text = line.strip_suffix(&[config.separator]).unwrap_or(&line);
+        if re.is_match(text) {
+            tail = Some(Vec::new());
+        }
+        if let Some(tail) = &mut tail {
+            tail.extend_from_slice(&line);
+        }
+        line.clear();
+    }
+    if let Some(tail) = tail {
+        io::stdout().write_all(&tail)?;
+    }
+    Ok(())
+}
+
+fn print_bytes<T: Read + Seek>(file: T, num_bytes: &TakeValue, total_bytes: i64) -> MyResult<u64> {
+    match get_start_index(num_bytes, total_bytes) {
+        Some(idx) => print_from(file, idx),
+        None => Ok(total_bytes as u64),
     }
 }
 
@@ -209,6 +989,8 @@ fn get_start_index(take_val: &TakeValue, total: i64) -> Option<u64> {
                 Some(0)
             }
         }
+        // Content-based start points are found by scanning instead
+        TakeValue::Since(_) | TakeValue::FromMatch(_) => None,
         TakeValue::TakeNum(rest) => {
             if total < *rest {
                 None
@@ -221,7 +1003,13 @@ fn get_start_index(take_val: &TakeValue, total: i64) -> Option<u64> {
 
 #[cfg(test)]
 mod tests {
-    use super::{count_lines_bytes, get_start_index, parse_num, TakeValue::*};
+    use super::{
+        find_char_start, find_since_start, find_tail_start, get_start_index, parse_num,
+        parse_separator, parse_time, parse_time_format, TakeValue::*, TimeFormat,
+        DEFAULT_TIME_FORMAT,
+    };
+    use chrono::NaiveDateTime;
+    use std::io::Cursor;
     #[test]
     fn test_parse_num() {
         // All integers should be interpreted as negative numbers
@@ -267,13 +1055,14 @@ mod tests {
         assert_eq!(res.unwrap_err().to_string(), "foo");
     }
     #[test]
-    fn test_count_lines_bytes() {
-        let res = count_lines_bytes("tests/inputs/one.txt");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), (1, 24));
-        let res = count_lines_bytes("tests/inputs/ten.txt");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), (10, 49));
+    fn test_parse_separator() {
+        assert_eq!(parse_separator(",").unwrap(), b',');
+        assert_eq!(parse_separator("\\n").unwrap(), b'\n');
+        assert_eq!(parse_separator("\\0").unwrap(), 0);
+        assert_eq!(parse_separator("\\x1e").unwrap(), 0x1e);
+        assert_eq!(parse_separator("\\x1").unwrap_err().to_string(), "\\x1");
+        assert_eq!(parse_separator("\\xzz").unwrap_err().to_string(), "\\xzz");
+        assert_eq!(parse_separator("ab").unwrap_err().to_string(), "ab");
     }
     #[test]
     fn test_get_start_index() {
@@ -301,4 +1090,79 @@ mod tests {
         // return 0 to print the whole file
         assert_eq!(get_start_index(&TakeNum(-20), 10), Some(0));
     }
+    #[test]
+    fn test_find_tail_start() {
+        let mut file = Cursor::new("a\nb\nc\n");
+        assert_eq!(find_tail_start(&mut file, 0, b'\n').unwrap(), 6);
+        assert_eq!(find_tail_start(&mut file, 1, b'\n').unwrap(), 4);
+        assert_eq!(find_tail_start(&mut file, 2, b'\n').unwrap(), 2);
+        assert_eq!(find_tail_start(&mut file, 5, b'\n').unwrap(), 0);
+        // The last line does not need a newline
+        let mut file = Cursor::new("a\nb");
+        assert_eq!(find_tail_start(&mut file, 1, b'\n').unwrap(), 2);
+        // Lines that span block boundaries
+        let line = format!("{}\n", "x".repeat(1000));
+        let mut file = Cursor::new(line.repeat(100));
+        assert_eq!(find_tail_start(&mut file, 3, b'\n').unwrap(), 97 * 1001);
+        assert_eq!(find_tail_start(&mut file, 100, b'\n').unwrap(), 0);
+        // Records with other separators
+        let mut file = Cursor::new("a\0b\nc\0");
+        assert_eq!(find_tail_start(&mut file, 1, 0).unwrap(), 2);
+        assert_eq!(find_tail_start(&mut file, 1, b'\n').unwrap(), 4);
+    }
+    #[test]
+    fn test_find_char_start() {
+        let mut file = Cursor::new("añb€\n");
+        assert_eq!(find_char_start(&mut file, 0).unwrap(), 8);
+        assert_eq!(find_char_start(&mut file, 1).unwrap(), 7);
+        assert_eq!(find_char_start(&mut file, 2).unwrap(), 4);
+        assert_eq!(find_char_start(&mut file, 4).unwrap(), 1);
+        assert_eq!(find_char_start(&mut file, 10).unwrap(), 0);
+    }
+    #[test]
+    fn test_parse_time() {
+        let time = |s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            parse_time("2026-10-16T10:00").unwrap(),
+            time("2026-10-16 10:00:00")
+        );
+        assert_eq!(
+            parse_time("2026-10-16 10:00:05").unwrap(),
+            time("2026-10-16 10:00:05")
+        );
+        assert_eq!(
+            parse_time("2026-10-16").unwrap(),
+            time("2026-10-16 00:00:00")
+        );
+        assert_eq!(
+            parse_time("yesterday").unwrap_err().to_string(),
+            "yesterday"
+        );
+    }
+    #[test]
+    fn test_parse_time_format() {
+        assert!(matches!(
+            parse_time_format("%d/%b/%Y:%H:%M:%S"),
+            Ok(TimeFormat::Strftime(_))
+        ));
+        assert!(matches!(
+            parse_time_format(r"^\[(.*?)\]"),
+            Ok(TimeFormat::Regex(_))
+        ));
+        assert!(parse_time_format("%Q").is_err());
+        assert!(parse_time_format("(").is_err());
+    }
+    #[test]
+    fn test_find_since_start() {
+        let format = parse_time_format(DEFAULT_TIME_FORMAT).unwrap();
+        let since = |s| parse_time(s).unwrap();
+        let log = "intro\n2026-10-16 09:00 a\n  more a\n2026-10-16 10:00 b\n2026-10-16 11:00 c\n";
+        let mut file = Cursor::new(log);
+        let find = |file: &mut Cursor<&str>, s| find_since_start(file, &since(s), &format, b'\n');
+        assert_eq!(find(&mut file, "2026-10-16T08:00").unwrap(), 0);
+        assert_eq!(find(&mut file, "2026-10-16T09:30").unwrap(), 34);
+        assert_eq!(find(&mut file, "2026-10-16T10:00").unwrap(), 34);
+        assert_eq!(find(&mut file, "2026-10-16T10:01").unwrap(), 53);
+        assert_eq!(find(&mut file, "2026-10-17").unwrap(), log.len() as u64);
+    }
 }
@@ -1,8 +1,7 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs::{self, File};
-use std::io::Read;
+use std::fs;
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -34,13 +33,8 @@ fn gen_bad_file() -> String {
 
 // --------------------------------------------------
 #[test]
-fn dies_no_args() -> TestResult {
-    Command::cargo_bin(PRG)?
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("USAGE"));
-
-    Ok(())
+fn no_args_reads_stdin() -> TestResult {
+    run_stdin(TEN, &[], "tests/expected/ten.txt.out")
 }
 
 // --------------------------------------------------
@@ -101,16 +95,25 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .stdout(expected);
+
+    Ok(())
+}
 
+// --------------------------------------------------
+fn run_stdin(input_file: &str, args: &[&str], expected_file: &str) -> TestResult {
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
+        .write_stdin(input)
         .assert()
-        .stdout(predicate::eq(expected.as_bytes() as &[u8]));
+        .success()
+        .stdout(expected);
 
     Ok(())
 }
@@ -829,3 +832,378 @@ fn multiple_files_c_plus_3() -> TestResult {
         "tests/expected/all.c+3.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_sleep_interval() -> TestResult {
+    let bad = random_string();
+    let expected = format!("invalid number of seconds -- {}", &bad);
+    Command::cargo_bin(PRG)?
+        .args(&["-f", "-s", &bad, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Follows `args` until a short-lived process exits, calling `change`
+// while tailr is running
+fn follow(args: &[&str], change: impl FnOnce()) -> Result<String, Box<dyn std::error::Error>> {
+    let mut sleeper = std::process::Command::new("sleep").arg("1.5").spawn()?;
+    let pid = sleeper.id().to_string();
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(&["-s", "0.1", "--pid", &pid])
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    std::thread::sleep(std::time::Duration::from_millis(400));
+    change();
+    sleeper.wait()?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_appended() -> TestResult {
+    let path = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    fs::write(&path, "one\ntwo\n")?;
+    let name = path.to_string_lossy().to_string();
+    let stdout = follow(&["-n", "1", "-f", &name], || {
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, b"three\n").unwrap();
+    })?;
+    fs::remove_file(&path)?;
+    assert_eq!(stdout, "two\nthree\n");
+
+    Ok(())
+}
+
+// Appends a line to a file tailed with `args`, returning the output
+fn follow_append(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    fs::write(&path, "one\ntwo\n")?;
+    let name = path.to_string_lossy().to_string();
+    let args: Vec<&str> = args.iter().copied().chain(["-f", &name]).collect();
+    let stdout = follow(&args, || {
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, b"three\n").unwrap();
+    })?;
+    fs::remove_file(&path)?;
+    Ok(stdout)
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_bytes_zero() -> TestResult {
+    assert_eq!(follow_append(&["-c", "0"])?, "three\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_bytes_past_eof() -> TestResult {
+    assert_eq!(follow_append(&["-c", "+100"])?, "three\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_rotated() -> TestResult {
+    let path = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    let rotated = path.with_extension("1");
+    fs::write(&path, "old\n")?;
+    let name = path.to_string_lossy().to_string();
+    let stdout = follow(&["-F", &name], || {
+        fs::rename(&path, &rotated).unwrap();
+        fs::write(&path, "new\n").unwrap();
+    })?;
+    fs::remove_file(&path)?;
+    fs::remove_file(&rotated)?;
+    assert_eq!(stdout, "old\nnew\n");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_two_files() -> TestResult {
+    let path_a = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    let path_b = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    fs::write(&path_a, "a1\n")?;
+    fs::write(&path_b, "b1\n")?;
+    let (name_a, name_b) = (path_a.to_string_lossy(), path_b.to_string_lossy());
+    let append = |path: &std::path::Path, text: &str| {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        std::io::Write::write_all(&mut file, text.as_bytes()).unwrap();
+    };
+    let stdout = follow(&["-F", &name_a, &name_b], || {
+        append(&path_b, "b2\n");
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        append(&path_a, "a2\n");
+    })?;
+    fs::remove_file(&path_a)?;
+    fs::remove_file(&path_b)?;
+    let expected = format!(
+        "==> {} <==\na1\n\n==> {} <==\nb1\nb2\n\n==> {} <==\na2\n",
+        name_a, name_b, name_a
+    );
+    assert_eq!(stdout, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_drains_renamed_file() -> TestResult {
+    let path = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    let rotated = path.with_extension("1");
+    fs::write(&path, "old\n")?;
+    let name = path.to_string_lossy().to_string();
+    let stdout = follow(&["-F", &name], || {
+        fs::rename(&path, &rotated).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        let mut file = fs::OpenOptions::new().append(true).open(&rotated).unwrap();
+        std::io::Write::write_all(&mut file, b"late\n").unwrap();
+        fs::write(&path, "new\n").unwrap();
+    })?;
+    fs::remove_file(&path)?;
+    fs::remove_file(&rotated)?;
+    assert_eq!(stdout, "old\nlate\nnew\n");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_n3() -> TestResult {
+    run_stdin(TEN, &["-n", "3", "-"], "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn stdin_n0() -> TestResult {
+    run_stdin(TEN, &["-n", "0"], "tests/expected/ten.txt.n0.out")
+}
+
+#[test]
+fn stdin_n200() -> TestResult {
+    run_stdin(TEN, &["-n", "200"], "tests/expected/ten.txt.n200.out")
+}
+
+#[test]
+fn stdin_n_plus_2() -> TestResult {
+    run_stdin(TEN, &["-n", "+2"], "tests/expected/ten.txt.n+2.out")
+}
+
+#[test]
+fn stdin_c8() -> TestResult {
+    run_stdin(TEN, &["-c", "8"], "tests/expected/ten.txt.c8.out")
+}
+
+#[test]
+fn stdin_c_plus_2() -> TestResult {
+    run_stdin(TEN, &["-c", "+2"], "tests/expected/ten.txt.c+2.out")
+}
+
+#[test]
+fn stdin_with_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-n", "1", ONE, "-"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("\n==> standard input <==\nb\n"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c3_raw_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["-c", "3", ONE])
+        .assert()
+        .success()
+        .stdout(&b"\x9b.\n"[..]);
+
+    Ok(())
+}
+
+#[test]
+fn one_chars_c3() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--chars", "-c", "3", ONE])
+        .assert()
+        .success()
+        .stdout("ś.\n");
+
+    Ok(())
+}
+
+#[test]
+fn one_chars_c_plus_2() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--chars", "-c", "+2", ONE])
+        .assert()
+        .success()
+        .stdout("ne line, four wordś.\n");
+
+    Ok(())
+}
+
+#[test]
+fn stdin_chars_c3() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--chars", "-c", "3"])
+        .write_stdin(fs::read(ONE)?)
+        .assert()
+        .success()
+        .stdout("ś.\n");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_separator() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--separator", "ab", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid separator -- ab"));
+
+    Ok(())
+}
+
+#[test]
+fn zero_terminated() -> TestResult {
+    let input = "one\0two\nthree\0four\0";
+    for args in [&["-z", "-n", "2"][..], &["-z", "-n", "2", "-"][..]] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout("two\nthree\0four\0");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_file() -> TestResult {
+    let path = std::env::temp_dir().join(format!("tailr-{}", random_string()));
+    fs::write(&path, "one\0two\nthree\0four\0")?;
+    let name = path.to_string_lossy().to_string();
+    let output = Command::cargo_bin(PRG)?
+        .args(&["-z", "-n", "2", &name])
+        .output()?;
+    fs::remove_file(&path)?;
+    assert_eq!(output.stdout, b"two\nthree\0four\0");
+
+    Ok(())
+}
+
+#[test]
+fn separator_hex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--separator", "\\x1e", "-n", "+2"])
+        .write_stdin("a\x1eb\x1ec\x1e")
+        .assert()
+        .success()
+        .stdout("b\x1ec\x1e");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+const LOG: &str = "tests/inputs/log.txt";
+
+fn run_log(args: &[&str], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .arg(LOG)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+    let input = fs::read(LOG)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_since() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--since", "noon", LOG])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time -- noon"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_time_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(&["--since", "2026-10-16", "--time-format", "(", LOG])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time format -- ("));
+
+    Ok(())
+}
+
+#[test]
+fn since() -> TestResult {
+    run_log(
+        &["--since", "2026-10-16T10:00"],
+        "2026-10-16 10:00:00 ERROR write failed\n  \
+         caused by: no space left on device\n\
+         2026-10-16 10:02:12 INFO retrying\n\
+         2026-10-16 10:05:45 ERROR write failed\n\
+         2026-10-16 10:06:00 INFO recovered\n",
+    )
+}
+
+#[test]
+fn since_before_start() -> TestResult {
+    run_log(&["--since", "2026-10-16"], &fs::read_to_string(LOG)?)
+}
+
+#[test]
+fn since_after_end() -> TestResult {
+    run_log(&["--since", "2026-10-17"], "")
+}
+
+#[test]
+fn since_strftime() -> TestResult {
+    run_log(
+        &["--since", "2026-10-16 10:05", "--time-format", "%Y-%m-%d %H:%M:%S"],
+        "2026-10-16 10:05:45 ERROR write failed\n\
+         2026-10-16 10:06:00 INFO recovered\n",
+    )
+}
+
+#[test]
+fn from_match() -> TestResult {
+    run_log(
+        &["--from-match", "ERROR"],
+        "2026-10-16 10:05:45 ERROR write failed\n\
+         2026-10-16 10:06:00 INFO recovered\n",
+    )
+}
+
+#[test]
+fn from_match_none() -> TestResult {
+    run_log(&["--from-match", "FATAL"], "")
+}
@@ -1,25 +1,199 @@
 use clap::{App, Arg};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Write};
+use std::ops::AddAssign;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
+    files0_from: Option<String>,
     lines: bool,
     words: bool,
     bytes: bool,
     chars: bool,
+    max_line_length: bool,
+    format: Format,
+    tokenizer: Tokenizer,
+    top: Option<usize>,
+}
+
+// What counts as a word
+#[derive(Debug)]
+pub enum Tokenizer {
+    Whitespace,
+    Regex(Regex),
+    Unicode,
+}
+
+impl Tokenizer {
+    fn tokens<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            Tokenizer::Whitespace => Box::new(text.split_whitespace()),
+            Tokenizer::Regex(re) => Box::new(
+                re.find_iter(text)
+                    .map(|m| m.as_str())
+                    .filter(|token| !token.is_empty()),
+            ),
+            // UAX #29, which makes each CJK ideograph its own word
+            Tokenizer::Unicode => Box::new(text.unicode_words()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct FileInfo {
+    #[serde(rename = "lines")]
     num_lines: usize,
+    #[serde(rename = "words")]
     num_words: usize,
+    #[serde(rename = "bytes")]
     num_bytes: usize,
+    #[serde(rename = "chars")]
     num_chars: usize,
+    max_line_length: usize,
+}
+
+impl FileInfo {
+    pub fn lines(&self) -> usize {
+        self.num_lines
+    }
+
+    pub fn words(&self) -> usize {
+        self.num_words
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    pub fn chars(&self) -> usize {
+        self.num_chars
+    }
+
+    /// The display width of the widest line
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+}
+
+/// Sums the counts, keeping the widest line of either
+impl AddAssign for FileInfo {
+    fn add_assign(&mut self, other: FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+    }
+}
+
+// One row of structured output; the total has no file
+#[derive(Serialize)]
+struct Record<'a> {
+    file: Option<&'a str>,
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    max_line_length: usize,
+}
+
+impl<'a> Record<'a> {
+    fn new(file: Option<&'a str>, info: &FileInfo) -> Self {
+        Record {
+            file,
+            lines: info.num_lines,
+            words: info.num_words,
+            bytes: info.num_bytes,
+            chars: info.num_chars,
+            max_line_length: info.max_line_length,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TokenRecord<'a> {
+    token: &'a str,
+    count: usize,
+}
+
+// Where counts are written, set up once per run
+enum Output {
+    Text(usize),
+    Json(io::Stdout),
+    Delimited(Box<csv::Writer<io::Stdout>>),
+}
+
+impl Output {
+    fn new(config: &Config, width: usize) -> Self {
+        let delimited = |delimiter| {
+            let writer = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(io::stdout());
+            Output::Delimited(Box::new(writer))
+        };
+        match config.format {
+            Format::Text => Output::Text(width),
+            Format::Json => Output::Json(io::stdout()),
+            Format::Csv => delimited(b','),
+            Format::Tsv => delimited(b'\t'),
+        }
+    }
+
+    fn write(&mut self, config: &Config, file: Option<&str>, info: &FileInfo) -> MyResult<()> {
+        match self {
+            Output::Text(width) => {
+                print_cols(config, info, file.unwrap_or("total"), *width);
+                Ok(())
+            }
+            _ => self.write_record(&Record::new(file, info)),
+        }
+    }
+
+    fn write_token(&mut self, token: &str, count: usize) -> MyResult<()> {
+        match self {
+            Output::Text(_) => {
+                println!("{:>7} {}", count, token);
+                Ok(())
+            }
+            _ => self.write_record(&TokenRecord { token, count }),
+        }
+    }
+
+    fn write_record(&mut self, record: &impl Serialize) -> MyResult<()> {
+        match self {
+            Output::Text(_) => unreachable!(),
+            Output::Json(out) => {
+                // One object per line, so records can be read as they come
+                let mut out = out.lock();
+                serde_json::to_writer(&mut out, record)?;
+                writeln!(out)?;
+            }
+            Output::Delimited(writer) => {
+                writer.serialize(record)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
 }
 
 pub fn get_args() -> MyResult<Config> {
@@ -60,8 +234,50 @@ pub fn get_args() -> MyResult<Config> {
                 .short("m")
                 .long("chars")
                 .help("Show character count")
-                .takes_value(false)
-                .conflicts_with("bytes"),
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("max_line_length")
+                .short("L")
+                .long("max-line-length")
+                .help("Show maximum display width of a line")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("files0_from")
+                .long("files0-from")
+                .value_name("F")
+                .help("Read NUL-separated file names from F (- for stdin)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format")
+                .possible_values(&["text", "json", "csv", "tsv"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::with_name("word_regex")
+                .long("word-regex")
+                .value_name("REGEX")
+                .help("Count matches of REGEX as words")
+                .takes_value(true)
+                .conflicts_with("unicode_words"),
+        )
+        .arg(
+            Arg::with_name("unicode_words")
+                .long("unicode-words")
+                .help("Split words at Unicode (UAX #29) word boundaries")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .value_name("N")
+                .help("Show the N most frequent words instead of counts")
+                .takes_value(true),
         )
         .get_matches();
 
@@ -69,121 +285,465 @@ pub fn get_args() -> MyResult<Config> {
     let mut words = matches.is_present("words");
     let mut bytes = matches.is_present("bytes");
     let chars = matches.is_present("chars");
+    let max_line_length = matches.is_present("max_line_length");
 
-    if [lines, words, bytes, chars].iter().all(|v| v == &false) {
+    if [lines, words, bytes, chars, max_line_length]
+        .iter()
+        .all(|v| v == &false)
+    {
         lines = true;
         words = true;
         bytes = true;
     }
 
+    let files = matches.values_of_lossy("files").unwrap();
+    let files0_from = matches.value_of("files0_from").map(String::from);
+    if files0_from.is_some() && matches.occurrences_of("files") > 0 {
+        return Err(From::from(format!(
+            "extra operand '{}'\nfile operands cannot be combined with --files0-from",
+            files[0]
+        )));
+    }
+
+    let format = match matches.value_of("format") {
+        Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        Some("tsv") => Format::Tsv,
+        _ => Format::Text,
+    };
+
+    let tokenizer = match matches.value_of("word_regex") {
+        Some(re) => {
+            Tokenizer::Regex(Regex::new(re).map_err(|_| format!("Invalid word regex \"{}\"", re))?)
+        }
+        None if matches.is_present("unicode_words") => Tokenizer::Unicode,
+        None => Tokenizer::Whitespace,
+    };
+
+    let top = matches
+        .value_of("top")
+        .map(parse_positive_int)
+        .transpose()
+        .map_err(|e| format!("illegal top count -- {}", e))?;
+
     Ok(Config {
-        files: matches.values_of_lossy("files").unwrap(),
+        files,
+        files0_from,
         lines,
         words,
         bytes,
-        chars: matches.is_present("chars"),
+        chars,
+        max_line_length,
+        format,
+        tokenizer,
+        top,
     })
 }
 
-fn format_field(value: usize, show: bool) -> String {
-    if show {
-        format!("{:>8}", value)
-    } else {
-        format!("")
+fn parse_positive_int(val: &str) -> MyResult<usize> {
+    match val.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(From::from(val)),
     }
 }
 
-fn print_cols(config: &Config, info: &FileInfo, filename: &String) {
-    let lines_str = format_field(info.num_lines, config.lines);
-    let words_str = format_field(info.num_words, config.words);
-    let bytes_str = format_field(info.num_bytes, config.bytes);
-    let chars_str = format_field(info.num_chars, config.chars);
-    let filename_str = if filename == "-" {
-        format!("")
-    } else {
-        format!(" {filename}")
-    };
+fn print_cols(config: &Config, info: &FileInfo, filename: &str, width: usize) {
+    // Same column order as GNU wc
+    let fields = [
+        (config.lines, info.num_lines),
+        (config.words, info.num_words),
+        (config.chars, info.num_chars),
+        (config.bytes, info.num_bytes),
+        (config.max_line_length, info.max_line_length),
+    ];
+    let mut line = fields
+        .iter()
+        .filter(|(show, _)| *show)
+        .map(|(_, value)| format!("{:>width$}", value))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if filename != "-" {
+        line.push(' ');
+        line.push_str(filename);
+    }
+    println!("{}", line);
+}
+
+// Like GNU wc, size the columns before counting from the total size of the
+// regular files, leaving room for 7 digits when any input is not regular.
+// A lone count for a single file is not padded at all.
+fn column_width(config: &Config) -> usize {
+    let num_fields = [
+        config.lines,
+        config.words,
+        config.chars,
+        config.bytes,
+        config.max_line_length,
+    ]
+    .iter()
+    .filter(|show| **show)
+    .count();
+    if config.files.len() == 1 && num_fields == 1 {
+        return 1;
+    }
+    let mut min_width = 1;
+    let mut total: u64 = 0;
+    for filename in &config.files {
+        match metadata(filename) {
+            Ok(meta) if meta.is_file() => total += meta.len(),
+            Ok(_) => min_width = 7,
+            Err(_) => {}
+        }
+    }
+    total.to_string().len().max(min_width)
+}
+
+#[cfg(unix)]
+fn metadata(filename: &str) -> io::Result<Metadata> {
+    use std::os::fd::AsFd;
+    match filename {
+        "-" => File::from(io::stdin().as_fd().try_clone_to_owned()?).metadata(),
+        _ => fs::metadata(filename),
+    }
+}
 
-    println!(
-        "{}{}{}{}",
-        lines_str,
-        words_str,
-        if config.bytes { bytes_str } else { chars_str },
-        filename_str
-    );
+#[cfg(not(unix))]
+fn metadata(filename: &str) -> io::Result<Metadata> {
+    match filename {
+        "-" => Err(io::ErrorKind::Unsupported.into()),
+        _ => fs::metadata(filename),
+    }
 }
 
 pub fn run(config: Config) -> MyResult<()> {
-    let num_files = config.files.len();
-    let mut infos = Vec::new();
-    for filename in config.files.iter() {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => {
-                let info = count(file)?;
-                print_cols(&config, &info, filename);
-                infos.push(info);
-            }
+    match &config.files0_from {
+        // The list is read as it is counted, so there is nothing to size the
+        // columns from up front
+        Some(list) => {
+            let mut out = Output::new(&config, 1);
+            count_files(&config, read_names(list)?, &mut out)
+        }
+        None => {
+            let names = config.files.iter().cloned().map(Ok);
+            let mut out = Output::new(&config, column_width(&config));
+            count_files(&config, names, &mut out)
         }
     }
-    if num_files > 1 {
-        let total = infos.iter().fold(
-            FileInfo {
-                num_lines: 0,
-                num_bytes: 0,
-                num_chars: 0,
-                num_words: 0,
-            },
-            |acc, info| FileInfo {
-                num_lines: acc.num_lines + info.num_lines,
-                num_bytes: acc.num_bytes + info.num_bytes,
-                num_chars: acc.num_chars + info.num_chars,
-                num_words: acc.num_words + info.num_words,
-            },
-        );
-        let name = String::from("total");
-        print_cols(&config, &total, &name)
+}
+
+// Counts the files on worker threads and prints them in the order given,
+// reading no further ahead in `names` than the workers can keep busy. An
+// `Err` name is a message to print in that file's place.
+fn count_files(
+    config: &Config,
+    names: impl Iterator<Item = Result<String, String>>,
+    out: &mut Output,
+) -> MyResult<()> {
+    // Structured output always has every count
+    let full =
+        config.words || config.chars || config.max_line_length || config.format != Format::Text;
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let workers = names.size_hint().1.map_or(workers, |n| n.min(workers));
+    let mut total = FileInfo::default();
+    let mut num_files = 0;
+    let histogram = Mutex::new(HashMap::new());
+    let (job_tx, job_rx) = mpsc::channel::<(usize, String)>();
+    let job_rx = Mutex::new(job_rx);
+    thread::scope(|scope| -> MyResult<()> {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..workers {
+            let (job_rx, tx, histogram) = (&job_rx, tx.clone(), &histogram);
+            scope.spawn(move || loop {
+                let job = job_rx.lock().unwrap().recv();
+                let (i, filename) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                let mut tokens: HashMap<String, usize> = HashMap::new();
+                let counted = config.top.is_some().then_some(&mut tokens);
+                // Box<dyn Error> can't be sent between threads
+                let result = open(&filename)
+                    .and_then(|file| count_with(file, full, &config.tokenizer, counted))
+                    .map(|info| (filename.clone(), info))
+                    .map_err(|e| format!("{}: {}", filename, e));
+                if result.is_ok() && !tokens.is_empty() {
+                    let mut histogram = histogram.lock().unwrap();
+                    for (token, count) in tokens {
+                        *histogram.entry(token).or_default() += count;
+                    }
+                }
+                if tx.send((i, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        let mut names = names.fuse().enumerate();
+        let mut in_flight = 0;
+        let mut done: HashMap<usize, Result<(String, FileInfo), String>> = HashMap::new();
+        let mut next_print = 0;
+        loop {
+            while in_flight + done.len() < workers * 4 {
+                match names.next() {
+                    Some((i, Ok(filename))) => {
+                        job_tx.send((i, filename)).unwrap();
+                        in_flight += 1;
+                    }
+                    Some((i, Err(err))) => {
+                        done.insert(i, Err(err));
+                    }
+                    None => break,
+                }
+                num_files += 1;
+            }
+            while let Some(result) = done.remove(&next_print) {
+                match result {
+                    Err(err) => eprintln!("{}", err),
+                    Ok((filename, info)) => {
+                        if config.top.is_none() {
+                            out.write(config, Some(&filename), &info)?;
+                        }
+                        total += info;
+                    }
+                }
+                next_print += 1;
+            }
+            if in_flight == 0 {
+                break;
+            }
+            let (i, result) = rx.recv().unwrap();
+            done.insert(i, result);
+            in_flight -= 1;
+        }
+        drop(job_tx);
+        Ok(())
+    })?;
+    if let Some(top) = config.top {
+        let mut histogram: Vec<(String, usize)> =
+            histogram.into_inner().unwrap().into_iter().collect();
+        histogram.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (token, count) in histogram.into_iter().take(top) {
+            out.write_token(&token, count)?;
+        }
+    } else if num_files > 1 || config.format != Format::Text {
+        out.write(config, None, &total)?;
     }
     Ok(())
 }
 
+// Yields the NUL-separated names in `list`, or a message for each name
+// that can't be used
+fn read_names(list: &str) -> MyResult<impl Iterator<Item = Result<String, String>>> {
+    let reader: Box<dyn BufRead> = match list {
+        "-" => Box::new(io::stdin().lock()),
+        _ => {
+            Box::new(BufReader::new(File::open(list).map_err(|e| {
+                format!("cannot open '{}' for reading: {}", list, e)
+            })?))
+        }
+    };
+    let list = list.to_string();
+    let mut split = reader.split(b'\0').enumerate();
+    let mut failed = false;
+    Ok(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let (i, name) = split.next()?;
+        Some(match name {
+            Err(e) => {
+                failed = true;
+                Err(format!("{}: read error: {}", list, e))
+            }
+            Ok(name) if name.is_empty() => {
+                Err(format!("{}:{}: invalid zero-length file name", list, i + 1))
+            }
+            Ok(name) if list == "-" && name == b"-" => Err(String::from(
+                "when reading file names from stdin, no file name of '-' allowed",
+            )),
+            Ok(name) => Ok(String::from_utf8_lossy(&name).into_owned()),
+        })
+    }))
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+        "-" => Ok(Box::new(BufReader::with_capacity(BUFFER_SIZE, io::stdin()))),
+        _ => Ok(Box::new(BufReader::with_capacity(
+            BUFFER_SIZE,
+            File::open(filename)?,
+        ))),
     }
 }
 
-pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
-    let mut num_lines = 0;
-    let mut num_words = 0;
-    let mut num_bytes = 0;
-    let mut num_chars = 0;
+const BUFFER_SIZE: usize = 1 << 16;
 
-    let mut line = String::new();
+pub fn count(file: impl BufRead) -> MyResult<FileInfo> {
+    count_with(file, true, &Tokenizer::Whitespace, None)
+}
+
+// Words are found by `tokenizer`, and tallied in `histogram` if given
+fn count_with(
+    file: impl BufRead,
+    full: bool,
+    tokenizer: &Tokenizer,
+    histogram: Option<&mut HashMap<String, usize>>,
+) -> MyResult<FileInfo> {
+    match (tokenizer, histogram) {
+        (Tokenizer::Whitespace, None) => count_buffers(file, full),
+        (tokenizer, histogram) => count_tokens(file, tokenizer, histogram),
+    }
+}
+
+// Lines and bytes only need a scan for newlines, so words, chars and line
+// widths are counted only when `full` is set
+fn count_buffers(mut file: impl BufRead, full: bool) -> MyResult<FileInfo> {
+    let mut counter = Counter::default();
     loop {
-        let bytes = file.read_line(&mut line)?;
-        if bytes == 0 {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
             break;
         }
-        num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
-        num_bytes += line.bytes().count();
+        counter.info.num_bytes += buf.len();
+        counter.info.num_lines += count_newlines(buf);
+        if full {
+            buf.iter().for_each(|&byte| counter.push(byte));
+        }
+        let len = buf.len();
+        file.consume(len);
+    }
+    Ok(counter.finish())
+}
+
+// Tokens don't span lines, so the text is split a line at a time
+fn count_tokens(
+    mut file: impl BufRead,
+    tokenizer: &Tokenizer,
+    mut histogram: Option<&mut HashMap<String, usize>>,
+) -> MyResult<FileInfo> {
+    let mut counter = Counter::default();
+    let mut num_words = 0;
+    let mut line = Vec::new();
+    while file.read_until(b'\n', &mut line)? > 0 {
+        counter.info.num_bytes += line.len();
+        counter.info.num_lines += count_newlines(&line);
+        line.iter().for_each(|&byte| counter.push(byte));
+        for token in tokenizer.tokens(&String::from_utf8_lossy(&line)) {
+            num_words += 1;
+            if let Some(histogram) = histogram.as_deref_mut() {
+                *histogram.entry(token.to_string()).or_default() += 1;
+            }
+        }
         line.clear();
     }
+    let mut info = counter.finish();
+    info.num_words = num_words;
+    Ok(info)
+}
 
-    Ok(FileInfo {
-        num_lines,
-        num_words,
-        num_bytes,
-        num_chars,
-    })
+// A branch-free count over fixed-size chunks that the compiler can vectorize
+fn count_newlines(buf: &[u8]) -> usize {
+    let mut chunks = buf.chunks_exact(64);
+    let mut num = 0;
+    for chunk in &mut chunks {
+        num += chunk
+            .iter()
+            .map(|&byte| (byte == b'\n') as usize)
+            .sum::<usize>();
+    }
+    num + chunks
+        .remainder()
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count()
+}
+
+// Decodes UTF-8 a byte at a time, keeping the partial character and
+// whether we are inside a word between buffers. Invalid bytes belong to
+// words but are not counted as characters.
+#[derive(Debug, Default)]
+struct Counter {
+    info: FileInfo,
+    in_word: bool,
+    line_width: usize,
+    code_point: u32,
+    needed: u32,
+}
+
+impl Counter {
+    fn push(&mut self, byte: u8) {
+        if self.needed > 0 {
+            if byte & 0xc0 == 0x80 {
+                self.code_point = (self.code_point << 6) | (byte & 0x3f) as u32;
+                self.needed -= 1;
+                if self.needed == 0 {
+                    self.end_char(char::from_u32(self.code_point));
+                }
+                return;
+            }
+            // A truncated sequence; this byte starts something new
+            self.needed = 0;
+            self.end_char(None);
+        }
+        match byte {
+            0x00..=0x7f => self.end_char(Some(byte as char)),
+            0xc2..=0xdf => self.start_char(byte & 0x1f, 1),
+            0xe0..=0xef => self.start_char(byte & 0x0f, 2),
+            0xf0..=0xf4 => self.start_char(byte & 0x07, 3),
+            _ => self.end_char(None),
+        }
+    }
+
+    fn start_char(&mut self, bits: u8, needed: u32) {
+        self.code_point = bits as u32;
+        self.needed = needed;
+    }
+
+    fn end_char(&mut self, c: Option<char>) {
+        if let Some(c) = c {
+            self.info.num_chars += 1;
+            self.add_width(c);
+        }
+        if c.is_some_and(char::is_whitespace) {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            self.info.num_words += 1;
+        }
+    }
+
+    // Tabs move to the next multiple of 8 and wide characters take two
+    // columns, as in GNU wc
+    fn add_width(&mut self, c: char) {
+        match c {
+            '\n' | '\r' | '\x0c' => {
+                self.end_line();
+            }
+            '\t' => self.line_width += 8 - self.line_width % 8,
+            _ => self.line_width += c.width().unwrap_or(0),
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.info.max_line_length = self.info.max_line_length.max(self.line_width);
+        self.line_width = 0;
+    }
+
+    fn finish(mut self) -> FileInfo {
+        if self.needed > 0 {
+            self.end_char(None);
+        }
+        self.end_line();
+        self.info
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{count, FileInfo};
+    use super::{count, count_newlines, count_with, Counter, FileInfo, Tokenizer};
+    use regex::Regex;
+    use std::collections::HashMap;
     use std::io::Cursor;
 
     #[test]
@@ -196,7 +756,94 @@ mod tests {
             num_words: 10,
             num_chars: 48,
             num_bytes: 48,
+            max_line_length: 46,
         };
         assert_eq!(info.unwrap(), expected);
     }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        let info = count(Cursor::new(b"caf\xc3\xa9 \xff\xfe ok\n\xe2\x82")).unwrap();
+        let expected = FileInfo {
+            num_lines: 1,
+            num_words: 4,
+            num_chars: 9,
+            num_bytes: 14,
+            max_line_length: 8,
+        };
+        assert_eq!(info, expected);
+    }
+
+    #[test]
+    fn test_counter_across_buffers() {
+        // "naïve\u{3000}x" split inside the ï and inside the ideographic space
+        let text = "naïve\u{3000}x".as_bytes();
+        for split in 0..text.len() {
+            let mut counter = Counter::default();
+            for part in [&text[..split], &text[split..]] {
+                part.iter().for_each(|&byte| counter.push(byte));
+            }
+            let info = counter.finish();
+            assert_eq!(
+                (info.num_words, info.num_chars),
+                (2, 7),
+                "split at {}",
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_newlines() {
+        let text = "a\n".repeat(100);
+        assert_eq!(count_newlines(text.as_bytes()), 100);
+        assert_eq!(count_newlines(&text.as_bytes()[1..150]), 75);
+    }
+
+    #[test]
+    fn test_file_info_add_and_serialize() {
+        let mut total = count(Cursor::new("a\tb\n")).unwrap();
+        total += count(Cursor::new("longer line")).unwrap();
+        assert_eq!(total.lines(), 1);
+        assert_eq!(total.words(), 4);
+        assert_eq!(total.bytes(), 15);
+        assert_eq!(total.chars(), 15);
+        assert_eq!(total.max_line_length(), 11);
+
+        let json = serde_json::to_string(&total).unwrap();
+        assert_eq!(
+            json,
+            r#"{"lines":1,"words":4,"bytes":15,"chars":15,"max_line_length":11}"#
+        );
+        assert_eq!(serde_json::from_str::<FileInfo>(&json).unwrap(), total);
+    }
+
+    #[test]
+    fn test_tokenizers() {
+        let text = "中文 foo_bar(x) \n";
+        let tokens = Tokenizer::Whitespace.tokens(text).collect::<Vec<_>>();
+        assert_eq!(tokens, ["中文", "foo_bar(x)"]);
+        let tokens = Tokenizer::Unicode.tokens(text).collect::<Vec<_>>();
+        assert_eq!(tokens, ["中", "文", "foo_bar", "x"]);
+        let re = Tokenizer::Regex(Regex::new(r"\w*").unwrap());
+        let tokens = re.tokens(text).collect::<Vec<_>>();
+        assert_eq!(tokens, ["中文", "foo_bar", "x"]);
+    }
+
+    #[test]
+    fn test_count_histogram() {
+        let mut histogram = HashMap::new();
+        let text = &b"a b\n\xffa\nb a"[..];
+        let info = count_with(
+            Cursor::new(text),
+            true,
+            &Tokenizer::Whitespace,
+            Some(&mut histogram),
+        )
+        .unwrap();
+        assert_eq!(info, count(Cursor::new(text)).unwrap());
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram["a"], 2);
+        assert_eq!(histogram["b"], 2);
+    }
 }
@@ -9,6 +9,7 @@ const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const TOKENS: &str = "tests/inputs/tokens.txt";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -25,19 +26,6 @@ fn gen_bad_file() -> String {
     }
 }
 
-// --------------------------------------------------
-#[test]
-fn dies_chars_and_bytes() -> TestResult {
-    Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains(
-            "The argument '--bytes' cannot be used with '--chars'",
-        ));
-    Ok(())
-}
-
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let expected = fs::read_to_string(expected_file)?;
@@ -116,6 +104,18 @@ fn fox_bytes_lines() -> TestResult {
     run(&["-l", "-c", FOX], "tests/expected/fox.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn fox_chars_bytes() -> TestResult {
+    run(&["-m", "-c", FOX], "tests/expected/fox.txt.mc.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_max_line_length() -> TestResult {
+    run(&["-L", FOX], "tests/expected/fox.txt.L.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal() -> TestResult {
@@ -158,6 +158,24 @@ fn atlamal_bytes_lines() -> TestResult {
     run(&["-l", "-c", ATLAMAL], "tests/expected/atlamal.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn atlamal_chars_bytes() -> TestResult {
+    run(&["-c", "-m", ATLAMAL], "tests/expected/atlamal.txt.mc.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_max_line_length() -> TestResult {
+    run(&["-L", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_lines_max_line_length() -> TestResult {
+    run(&["-lL", ATLAMAL], "tests/expected/atlamal.txt.lL.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal_stdin() -> TestResult {
@@ -212,3 +230,254 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn test_all_chars_bytes() -> TestResult {
+    run(&["-mc", EMPTY, FOX, ATLAMAL], "tests/expected/all.mc.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_json() -> TestResult {
+    run(
+        &["--format", "json", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_csv() -> TestResult {
+    run(
+        &["--format", "csv", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_tsv() -> TestResult {
+    run(
+        &["-l", "--format", "tsv", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.tsv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_max_line_length() -> TestResult {
+    run(&["-L", EMPTY, FOX, ATLAMAL], "tests/expected/all.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_max_line_length_words() -> TestResult {
+    run(
+        &["-L", "-w", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.wL.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn keeps_order_around_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, FOX, &bad, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .write_stdin(&b"caf\xc3\xa9 \xff\xfe ok\n\xe2\x82"[..])
+        .assert()
+        .success()
+        .stdout("      1       4      14\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn max_line_length_tabs_and_wide_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-L"])
+        .write_stdin("a\tb\u{4e2d}c\nabcd\rx\n")
+        .assert()
+        .success()
+        .stdout("12\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt"])
+        .assert()
+        .success()
+        .stdout(
+            "1 9 48 tests/inputs/fox.txt\n\
+             4 29 177 tests/inputs/atlamal.txt\n\
+             5 38 225 total\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let bad = gen_bad_file();
+    let input = format!("{}\0{}\0\0{}\0", FOX, bad, EMPTY);
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--files0-from=-"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(format!("1 {}\n0 {}\n1 total\n", FOX, EMPTY))
+        .stderr(predicate::str::is_match(format!(
+            "^{}: .* [(]os error 2[)]\n-:3: invalid zero-length file name\n$",
+            bad
+        ))?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin_rejects_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=-"])
+        .write_stdin(format!("-\0{}\0", FOX))
+        .assert()
+        .success()
+        .stdout(format!("1 9 48 {}\n1 9 48 total\n", FOX))
+        .stderr(predicate::str::contains("no file name of '-' allowed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_operands() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "file operands cannot be combined with --files0-from",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_files0_from() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!(
+            "cannot open '{}' for reading: ",
+            bad
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json_stdin_has_total() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=json"])
+        .write_stdin("a b\n")
+        .assert()
+        .success()
+        .stdout(
+            "{\"file\":\"-\",\"lines\":1,\"words\":2,\"bytes\":4,\"chars\":4,\"max_line_length\":3}\n\
+             {\"file\":null,\"lines\":1,\"words\":2,\"bytes\":4,\"chars\":4,\"max_line_length\":3}\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "xml", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'xml' isn't a valid value"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_words() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--unicode-words", TOKENS])
+        .assert()
+        .success()
+        .stdout(format!(" 2  9 42 {}\n", TOKENS));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-regex", "[A-Za-z_]+", TOKENS])
+        .assert()
+        .success()
+        .stdout(format!("5 {}\n", TOKENS));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn top_words() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "3", ATLAMAL, FOX])
+        .assert()
+        .success()
+        .stdout("      2 er\n      2 var\n      1 Frétt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn top_unicode_words_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "2", "--unicode-words", "--format", "json", TOKENS])
+        .assert()
+        .success()
+        .stdout("{\"token\":\"文\",\"count\":2}\n{\"token\":\"baz\",\"count\":1}\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_top() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal top count -- 0"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_word_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--word-regex", "(", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid word regex \"(\""));
+    Ok(())
+}